@@ -0,0 +1,220 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Field, Fields, Index};
+
+#[proc_macro_derive(AsStd140)]
+pub fn derive_as_std140(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(
+        input,
+        quote!(AsStd140),
+        quote!(as_std140),
+        quote!(Std140Bytes),
+    )
+    .unwrap_or_else(syn::Error::into_compile_error)
+    .into()
+}
+
+#[proc_macro_derive(AsStd430)]
+pub fn derive_as_std430(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(
+        input,
+        quote!(AsStd430),
+        quote!(as_std430),
+        quote!(Std430Bytes),
+    )
+    .unwrap_or_else(syn::Error::into_compile_error)
+    .into()
+}
+
+#[proc_macro_derive(LayoutInfo)]
+pub fn derive_layout_info(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_layout_info(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(AsMetal)]
+pub fn derive_as_metal(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(input, quote!(AsMetal), quote!(as_metal), quote!(MetalBytes))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn struct_fields<'a>(input: &'a DeriveInput, trait_name: &TokenStream) -> syn::Result<&'a Fields> {
+    match &input.data {
+        Data::Struct(data) => Ok(&data.fields),
+        _ => Err(syn::Error::new_spanned(
+            &input.ident,
+            format!("`{trait_name}` can only be derived for structs"),
+        )),
+    }
+}
+
+// the name of each field along with the expression accessing it on `self`
+fn field_accessors(fields: &Fields) -> Vec<(String, TokenStream, &Field)> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| match &field.ident {
+            Some(name) => (name.to_string(), quote!(#name), field),
+            None => {
+                let index = Index::from(i);
+                (i.to_string(), quote!(#index), field)
+            }
+        })
+        .collect()
+}
+
+fn expand(
+    mut input: DeriveInput,
+    trait_name: TokenStream,
+    method: TokenStream,
+    bytes: TokenStream,
+) -> syn::Result<TokenStream> {
+    let fields = struct_fields(&input, &trait_name)?;
+
+    // write every field in declaration order, spanned to the field so that a field type
+    // missing the trait is reported on that field
+    let writes = field_accessors(fields)
+        .into_iter()
+        .map(|(_, access, field)| {
+            let ty = &field.ty;
+
+            quote_spanned!(field.span()=>
+                buf.write::<#ty>(&self.#access);
+            )
+        })
+        .collect::<Vec<_>>();
+
+    for param in input.generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::gpu_bytes::#trait_name));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::gpu_bytes::#trait_name for #name #ty_generics #where_clause {
+            fn #method(&self) -> ::gpu_bytes::#bytes {
+                let mut buf = ::gpu_bytes::#bytes::new();
+
+                #(#writes)*
+
+                buf.align();
+                buf
+            }
+        }
+    })
+}
+
+fn expand_layout_info(mut input: DeriveInput) -> syn::Result<TokenStream> {
+    let fields = struct_fields(&input, &quote!(LayoutInfo))?;
+
+    // each field is placed like the layout traits would write it, from its type alone
+    let placements = field_accessors(fields)
+        .into_iter()
+        .map(|(name, _, field)| {
+            let ty = &field.ty;
+
+            quote_spanned!(field.span()=>
+                let size = <#ty as ::gpu_bytes::ShaderSize>::shader_size(layout);
+                offset = offset.next_multiple_of(
+                    <#ty as ::gpu_bytes::ShaderAlignment>::shader_alignment(layout).max(1),
+                );
+
+                fields.push(::gpu_bytes::FieldInfo {
+                    name: #name,
+                    wgsl_type: <#ty as ::gpu_bytes::WgslType>::wgsl_type(),
+                    offset,
+                    size,
+                });
+
+                offset += size;
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // a struct is aligned to its most aligned field
+    let max_alignment = |constant: TokenStream| {
+        let alignments = fields.iter().map(|field| {
+            let ty = &field.ty;
+
+            quote_spanned!(field.span()=>
+                if <#ty as ::gpu_bytes::ShaderAlignment>::#constant > align {
+                    align = <#ty as ::gpu_bytes::ShaderAlignment>::#constant;
+                }
+            )
+        });
+
+        quote!({
+            let mut align = 1;
+            #(#alignments)*
+            align
+        })
+    };
+
+    let std140_alignment = max_alignment(quote!(STD140_ALIGNMENT));
+    let std430_alignment = max_alignment(quote!(STD430_ALIGNMENT));
+    let metal_alignment = max_alignment(quote!(METAL_ALIGNMENT));
+
+    for param in input.generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::gpu_bytes::ShaderSize));
+        param
+            .bounds
+            .push(parse_quote!(::gpu_bytes::ShaderAlignment));
+        param.bounds.push(parse_quote!(::gpu_bytes::WgslType));
+    }
+
+    let name = &input.ident;
+    let name_str = name.to_string();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::gpu_bytes::WgslType for #name #ty_generics #where_clause {
+            fn wgsl_type() -> ::std::string::String {
+                ::std::string::String::from(#name_str)
+            }
+        }
+
+        impl #impl_generics ::gpu_bytes::ShaderAlignment for #name #ty_generics #where_clause {
+            const STD140_ALIGNMENT: usize = #std140_alignment;
+            const STD430_ALIGNMENT: usize = #std430_alignment;
+            const METAL_ALIGNMENT: usize = #metal_alignment;
+        }
+
+        impl #impl_generics ::gpu_bytes::ShaderSize for #name #ty_generics #where_clause {
+            fn shader_size(layout: ::gpu_bytes::Layout) -> usize {
+                Self::layout_info(layout).size
+            }
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Describes the offset, size and WGSL type of each field in the given layout.
+            pub fn layout_info(layout: ::gpu_bytes::Layout) -> ::gpu_bytes::LayoutInfo {
+                let mut offset = 0usize;
+                let mut fields = ::std::vec::Vec::new();
+
+                #(#placements)*
+
+                let alignment =
+                    <Self as ::gpu_bytes::ShaderAlignment>::shader_alignment(layout);
+
+                ::gpu_bytes::LayoutInfo {
+                    name: #name_str,
+                    size: offset.next_multiple_of(alignment),
+                    alignment,
+                    fields,
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,43 @@
+use crate::{AsMetal, AsStd140, AsStd430, MetalBytes, Std140Bytes, Std430Bytes};
+
+// unlike a `Vec<T>`, only the elements that are present are written, like a slice
+impl<T: AsStd140, const N: usize> AsStd140 for arrayvec::ArrayVec<T, N> {
+    fn as_std140(&self) -> Std140Bytes {
+        self.as_slice().as_std140()
+    }
+}
+
+impl<T: AsStd430, const N: usize> AsStd430 for arrayvec::ArrayVec<T, N> {
+    fn as_std430(&self) -> Std430Bytes {
+        self.as_slice().as_std430()
+    }
+}
+
+impl<T: AsMetal, const N: usize> AsMetal for arrayvec::ArrayVec<T, N> {
+    fn as_metal(&self) -> MetalBytes {
+        self.as_slice().as_metal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrayvec::ArrayVec;
+
+    use super::*;
+
+    #[test]
+    fn array_vec_matches_slice() {
+        let mut v = ArrayVec::<u32, 4>::new();
+        v.push(1);
+        v.push(2);
+
+        assert_eq!(
+            v.as_std140().as_slice(),
+            (&[1u32, 2][..]).as_std140().as_slice()
+        );
+        assert_eq!(
+            v.as_std430().as_slice(),
+            (&[1u32, 2][..]).as_std430().as_slice()
+        );
+    }
+}
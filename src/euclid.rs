@@ -0,0 +1,70 @@
+use crate::{AsMetal, AsStd140, AsStd430, MetalBytes, Std140Bytes, Std430Bytes};
+
+// euclid types are written exactly like the glam type with the same shape
+macro_rules! euclid_impl_std140_std430 {
+    ($datatype:ty, $($param:ident),* => |$value:ident| $to_glam:expr) => {
+        impl<$($param),*> AsStd140 for $datatype {
+            fn as_std140(&self) -> Std140Bytes {
+                let $value = self;
+                $to_glam.as_std140()
+            }
+        }
+
+        impl<$($param),*> AsStd430 for $datatype {
+            fn as_std430(&self) -> Std430Bytes {
+                let $value = self;
+                $to_glam.as_std430()
+            }
+        }
+
+        impl<$($param),*> AsMetal for $datatype {
+            fn as_metal(&self) -> MetalBytes {
+                let $value = self;
+                $to_glam.as_metal()
+            }
+        }
+    };
+}
+
+euclid_impl_std140_std430!(euclid::Vector2D<f32, U>, U => |v| glam::Vec2::new(v.x, v.y));
+euclid_impl_std140_std430!(euclid::Vector3D<f32, U>, U => |v| glam::Vec3::new(v.x, v.y, v.z));
+euclid_impl_std140_std430!(euclid::Point2D<f32, U>, U => |p| glam::Vec2::new(p.x, p.y));
+euclid_impl_std140_std430!(euclid::Point3D<f32, U>, U => |p| glam::Vec3::new(p.x, p.y, p.z));
+
+// euclid stores its terms so that `to_array` is column major for column vectors, like glam
+euclid_impl_std140_std430!(
+    euclid::Transform3D<f32, Src, Dst>, Src, Dst => |t| glam::Mat4::from_cols_array(&t.to_array())
+);
+
+#[cfg(test)]
+mod tests {
+    use glam::{Mat4, Vec3};
+
+    use super::*;
+
+    #[test]
+    fn vector3d_matches_glam() {
+        let v = euclid::default::Vector3D::new(1.0f32, 2.0, 3.0);
+
+        assert_eq!(
+            v.as_std140().as_slice(),
+            Vec3::new(1.0, 2.0, 3.0).as_std140().as_slice()
+        );
+        assert_eq!(
+            v.as_std430().as_slice(),
+            Vec3::new(1.0, 2.0, 3.0).as_std430().as_slice()
+        );
+    }
+
+    #[test]
+    fn transform3d_matches_glam() {
+        let t = euclid::default::Transform3D::translation(1.0f32, 2.0, 3.0);
+
+        assert_eq!(
+            t.as_std140().as_slice(),
+            Mat4::from_translation(Vec3::new(1.0, 2.0, 3.0))
+                .as_std140()
+                .as_slice()
+        );
+    }
+}
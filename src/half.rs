@@ -0,0 +1,84 @@
+use half::f16;
+
+use crate::{write_scalar_le, AsMetal, AsStd140, AsStd430, MetalBytes, Std140Bytes, Std430Bytes};
+
+/// A `vec2<f16>`, which is 4 bytes and aligned to 4. A plain `[f16; 2]` is an array instead.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct F16Vec2(pub [f16; 2]);
+
+/// A `vec4<f16>`, which is 8 bytes and aligned to 8. A plain `[f16; 4]` is an array instead.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct F16Vec4(pub [f16; 4]);
+
+// the f16 types are written like the 32-bit primitives, with 2-byte components
+macro_rules! half_impl_std140_std430 {
+    ($datatype:ty, align = $align:literal, |$value:ident| $data:expr) => {
+        impl AsStd140 for $datatype {
+            fn as_std140(&self) -> Std140Bytes {
+                let mut buf = Std140Bytes::new();
+                let $value = self;
+
+                write_scalar_le(&mut buf.gpu_bytes, $data, 2, $align);
+
+                buf
+            }
+        }
+
+        impl AsStd430 for $datatype {
+            fn as_std430(&self) -> Std430Bytes {
+                let mut buf = Std430Bytes::new();
+                let $value = self;
+
+                write_scalar_le(&mut buf.gpu_bytes, $data, 2, $align);
+
+                buf
+            }
+        }
+
+        impl AsMetal for $datatype {
+            fn as_metal(&self) -> MetalBytes {
+                let mut buf = MetalBytes::new();
+                let $value = self;
+
+                write_scalar_le(&mut buf.gpu_bytes, $data, 2, $align);
+
+                buf
+            }
+        }
+    };
+}
+
+half_impl_std140_std430!(f16, align = 2, |v| v);
+half_impl_std140_std430!(F16Vec2, align = 4, |v| &v.0);
+half_impl_std140_std430!(F16Vec4, align = 8, |v| &v.0);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn f16_scalar_padding() {
+        let mut buf = Std430Bytes::new();
+        buf.write(&f16::ONE).write(&1.0f32);
+
+        assert_eq!(buf.padding_bytes(), 2);
+        assert_eq!(buf.as_slice()[..2], f16::ONE.to_le_bytes());
+        assert_eq!(buf.as_slice().len(), 8);
+    }
+
+    #[test]
+    fn f16_array_stride() {
+        let values = [f16::ONE, f16::ZERO];
+
+        // std140 still rounds array elements up to 16 bytes
+        assert_eq!(values.as_std140().as_slice().len(), 32);
+        assert_eq!(values.as_std430().as_slice().len(), 4);
+
+        assert_eq!(F16Vec2(values).as_std140().as_slice().len(), 4);
+        assert_eq!(F16Vec4([f16::ONE; 4]).as_std430().alignment(), 8);
+        assert_eq!(
+            F16Vec2(values).as_std140().as_slice(),
+            &[f16::ONE.to_le_bytes(), f16::ZERO.to_le_bytes()].concat()[..]
+        );
+    }
+}
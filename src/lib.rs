@@ -1,19 +1,199 @@
-trait AsGpuBytes {
-    fn as_gpu_bytes(&self) -> GpuBytes;
+#[cfg(feature = "arrayvec")]
+mod arrayvec;
+#[cfg(feature = "euclid")]
+mod euclid;
+#[cfg(feature = "half")]
+mod half;
+#[cfg(feature = "half")]
+pub use self::half::{F16Vec2, F16Vec4};
+#[cfg(feature = "mint")]
+mod mint;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "wgpu")]
+mod wgpu;
+
+/// Derives [`WgslType`], [`ShaderSize`], [`ShaderAlignment`] and an associated
+/// `layout_info(layout)` describing the offset of each field, computed from the field types so
+/// that no value is needed.
+#[cfg(feature = "derive")]
+pub use gpu_bytes_derive::LayoutInfo;
+/// Derives the layout traits for a struct by writing each of its fields in declaration order.
+#[cfg(feature = "derive")]
+pub use gpu_bytes_derive::{AsMetal, AsStd140, AsStd430};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpuBytesError {
+    /// An alignment that is zero or not a power of two was given.
+    InvalidAlignment(usize),
+    /// The length of the bytes is not a multiple of their claimed alignment.
+    MisalignedLength { len: usize, alignment: usize },
+    /// The buffer would grow past the maximum size it is allowed to have.
+    ExceedsMaxSize { size: usize, max_size: usize },
+    /// Bytes laid out for one layout were used where another layout was expected.
+    LayoutMismatch { expected: Layout, actual: Layout },
+    /// A `Vec<T>` without capacity was converted, so the size of its gpu representation is unknown.
+    ZeroCapacityVec,
+    /// An array stride can't hold its elements, or doesn't keep them aligned.
+    InvalidStride {
+        stride: usize,
+        size: usize,
+        alignment: usize,
+    },
+    /// There weren't enough bytes left to read a value from.
+    TooShort { len: usize, needed: usize },
+    /// The buffer doesn't have the size the shader expects.
+    SizeMismatch { expected: usize, actual: usize },
+    /// Arrays that are combined element by element have different lengths.
+    LengthMismatch { left: usize, right: usize },
+}
+
+impl std::fmt::Display for GpuBytesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GpuBytesError::InvalidAlignment(align) => write!(f, "invalid alignment {align}"),
+            GpuBytesError::MisalignedLength { len, alignment } => write!(
+                f,
+                "byte length {len} is not a multiple of the alignment {alignment}"
+            ),
+            GpuBytesError::ExceedsMaxSize { size, max_size } => write!(
+                f,
+                "buffer size {size} exceeds the maximum size of {max_size}"
+            ),
+            GpuBytesError::LayoutMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "expected bytes in the {expected:?} layout, got {actual:?}"
+                )
+            }
+            GpuBytesError::ZeroCapacityVec => write!(
+                f,
+                "A Vec<T> should have an initial capacity before being converted to gpu layout"
+            ),
+            GpuBytesError::InvalidStride {
+                stride,
+                size,
+                alignment,
+            } => write!(
+                f,
+                "array stride {stride} can't hold elements of size {size} aligned to {alignment}"
+            ),
+            GpuBytesError::TooShort { len, needed } => {
+                write!(f, "needed {needed} bytes to read a value, got {len}")
+            }
+            GpuBytesError::SizeMismatch { expected, actual } => {
+                write!(f, "expected a buffer of {expected} bytes, got {actual}")
+            }
+            GpuBytesError::LengthMismatch { left, right } => {
+                write!(f, "arrays of lengths {left} and {right} can't be combined")
+            }
+        }
+    }
+}
+
+impl std::error::Error for GpuBytesError {}
+
+/// The state of a builder at some point, to which it can be restored later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuilderSnapshot {
+    len: usize,
+    alignment: usize,
+    padding: usize,
+    data_len: usize,
+}
+
+impl BuilderSnapshot {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn alignment(&self) -> usize {
+        self.alignment
+    }
+}
+
+/// Padding that was inserted in front of a value passed to `write`, recorded by builders created
+/// with padding warnings enabled. This often means that the values were meant to be an array, or
+/// that the shader struct has them in a different order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaddingWarning {
+    /// The offset at which the padding starts.
+    pub offset: usize,
+    /// The number of padding bytes.
+    pub padding: usize,
+}
+
+/// Where a value passed to `write` ended up, recorded by builders created with debug offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldOffset {
+    pub offset: usize,
+    pub size: usize,
+    pub alignment: usize,
+}
+
+/// The location of a `u32` size header reserved by `reserve_size_header`, to be filled in by
+/// `finalize_size_header` once the buffer is complete.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[must_use]
+pub struct SizeHeaderHandle {
+    offset: usize,
+}
+
+/// Conversion into the bytes of any [`Layout`], implemented for every type that supports std140
+/// and std430. Values are written in the Metal layout through [`MetalBytes`] and [`AsMetal`]
+/// instead, so a [`GpuBytes`] in the Metal layout only accepts already laid out bytes.
+pub trait AsGpuBytes {
+    fn as_gpu_bytes(&self, layout: Layout) -> GpuBytes;
 }
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
-enum Layout {
+pub enum Layout {
     #[default]
     Std140,
     Std430,
+    /// The layout of Metal shading language buffers, where 3-component vectors are 16 bytes.
+    Metal,
+}
+
+impl Layout {
+    /// The alignment of an array element whose type has `element_align` alignment. In std140,
+    /// array elements are aligned to a multiple of 16, while in std430 and Metal they keep their
+    /// own alignment.
+    pub const fn array_element_alignment(&self, element_align: usize) -> usize {
+        match self {
+            Layout::Std140 => element_align.next_multiple_of(16),
+            Layout::Std430 | Layout::Metal => element_align,
+        }
+    }
 }
 
+/// A builder for any [`Layout`], which [`Std140Bytes`], [`Std430Bytes`] and [`MetalBytes`] wrap.
 #[derive(Debug, Default, Clone)]
-struct GpuBytes {
+pub struct GpuBytes {
     bytes: Vec<u8>,
     alignment: usize,
     layout: Layout,
+    // total number of bytes that were inserted as padding rather than data
+    padding: usize,
+    // length of the buffer up to and including the last byte of data, excluding trailing padding
+    data_len: usize,
+    // field names that still have to be written, in order
+    expected_fields: std::collections::VecDeque<String>,
+    // length and alignment from before the most recent `align_to`, if nothing was written since
+    last_align: Option<(usize, usize)>,
+    // whether matrices written to this buffer are stored row major
+    transpose_matrices: bool,
+    // for a buffer holding just a square matrix, its column count, column stride and component
+    // size, so that it can be transposed when written to a buffer that transposes matrices
+    matrix: Option<(usize, usize, usize)>,
+    // every `write` that had to insert padding, if warnings are enabled
+    padding_warnings: Option<Vec<PaddingWarning>>,
+    // where every `write` ended up, if debug offsets are enabled
+    debug_offsets: Option<Vec<FieldOffset>>,
 }
 
 impl GpuBytes {
@@ -24,41 +204,249 @@ impl GpuBytes {
         }
     }
 
-    fn write_slice(&mut self, data: &[u8], align: usize) {
-        self.alignment = self.alignment.max(align);
+    fn from_bytes(layout: Layout, bytes: Vec<u8>, alignment: usize) -> Self {
+        Self {
+            data_len: bytes.len(),
+            bytes,
+            alignment,
+            layout,
+            ..Default::default()
+        }
+    }
+
+    fn from_bytes_checked(
+        layout: Layout,
+        bytes: Vec<u8>,
+        alignment: usize,
+    ) -> Result<Self, GpuBytesError> {
+        if !alignment.is_power_of_two() {
+            return Err(GpuBytesError::InvalidAlignment(alignment));
+        }
+
+        if !bytes.len().is_multiple_of(alignment) {
+            return Err(GpuBytesError::MisalignedLength {
+                len: bytes.len(),
+                alignment,
+            });
+        }
+
+        Ok(Self::from_bytes(layout, bytes, alignment))
+    }
+
+    fn pad_to(&mut self, align: usize) {
+        // an alignment of zero, e.g. that of an empty buffer, places no constraint on the offset
+        let align = align.max(1);
+        debug_assert!(
+            align.is_power_of_two(),
+            "alignment {align} is not a power of two"
+        );
 
         let offset = self.bytes.len();
         let padding = (align - (offset % align)) % align;
 
-        self.bytes.extend(std::iter::repeat(0u8).take(padding));
+        self.bytes.extend(std::iter::repeat_n(0u8, padding));
+        self.padding += padding;
+
+        if padding > 0 {
+            self.matrix = None;
+        }
+    }
+
+    // pads with zeros until the buffer is at least `len` bytes long
+    fn pad_to_len(&mut self, len: usize) {
+        let padding = len.saturating_sub(self.bytes.len());
+
+        self.bytes.extend(std::iter::repeat_n(0u8, padding));
+        self.padding += padding;
+
+        if padding > 0 {
+            self.matrix = None;
+        }
+    }
+
+    fn write_slice(&mut self, data: &[u8], align: usize) {
+        self.alignment = self.alignment.max(align);
+        self.pad_to(align);
 
         self.bytes.extend_from_slice(data);
+        self.last_align = None;
+        self.matrix = None;
+
+        if !data.is_empty() {
+            self.data_len = self.bytes.len();
+        }
+    }
+
+    // appends the bytes of `other` directly, without padding in front of them
+    fn append(&mut self, other: &GpuBytes) {
+        if other.data_len > 0 {
+            self.data_len = self.bytes.len() + other.data_len;
+        }
+
+        self.bytes.extend_from_slice(&other.bytes);
+        self.padding += other.padding;
+        self.last_align = None;
+        self.matrix = None;
+    }
+
+    // transposes a matrix in place if this buffer transposes matrices, by swapping each component
+    // with its mirror across the diagonal, which leaves the padding after each column in place
+    fn matrix_order(&self, mut data: GpuBytes) -> GpuBytes {
+        let Some((columns, stride, component)) = data.matrix.take() else {
+            return data;
+        };
+
+        if self.transpose_matrices {
+            for column in 0..columns {
+                for row in column + 1..columns {
+                    for i in 0..component {
+                        data.bytes.swap(
+                            column * stride + row * component + i,
+                            row * stride + column * component + i,
+                        );
+                    }
+                }
+            }
+        }
+
+        data
+    }
+
+    // aligns the buffer to the data and appends it, returning the amount of padding inserted
+    fn write_data(&mut self, data: &GpuBytes) -> usize {
+        // empty buffers have an alignment of zero, which places no constraint on the offset
+        let alignment = data.alignment.max(1);
+        debug_assert!(
+            alignment.is_power_of_two(),
+            "alignment {alignment} of written data is not a power of two"
+        );
+
+        let offset = self.bytes.len();
+
+        self.alignment = self.alignment.max(alignment);
+        self.pad_to(alignment);
+
+        let padding = self.bytes.len() - offset;
+
+        self.append(data);
+        padding
     }
 
     pub fn write<T: AsGpuBytes>(&mut self, data: &T) -> &mut Self {
-        let data = data.as_gpu_bytes();
+        let data = self.matrix_order(data.as_gpu_bytes(self.layout));
 
-        self.write_slice(data.as_slice(), data.alignment);
+        let offset = self.bytes.len();
+        let padding = self.write_data(&data);
+
+        if let Some(warnings) = self.padding_warnings.as_mut() {
+            if padding > 0 {
+                warnings.push(PaddingWarning { offset, padding });
+            }
+        }
+
+        self.record_offset(offset + padding, data.bytes.len(), data.alignment);
         self
     }
 
+    fn record_offset(&mut self, offset: usize, size: usize, alignment: usize) {
+        if let Some(offsets) = self.debug_offsets.as_mut() {
+            offsets.push(FieldOffset {
+                offset,
+                size,
+                alignment,
+            });
+        }
+    }
+
+    // drops the warnings and offsets recorded for anything past `len`, after truncating to it
+    fn truncate_records(&mut self, len: usize) {
+        if let Some(warnings) = self.padding_warnings.as_mut() {
+            warnings.retain(|warning| warning.offset < len);
+        }
+
+        if let Some(offsets) = self.debug_offsets.as_mut() {
+            offsets.retain(|field| field.offset < len);
+        }
+    }
+
     pub fn write_array<T: AsGpuBytes>(&mut self, data: impl IntoIterator<Item = T>) -> &mut Self {
-        for elem in data.into_iter() {
-            let mut elem = elem.as_gpu_bytes();
+        self.write_array_offsets(data);
+        self
+    }
 
-            match self.layout {
-                Layout::Std140 => {
-                    elem.align_to(elem.alignment.next_multiple_of(16));
-                }
-                Layout::Std430 => {
-                    elem.align();
-                }
+    // writes `data` as an array, returning the offset of each element
+    fn write_array_offsets<T: AsGpuBytes>(
+        &mut self,
+        data: impl IntoIterator<Item = T>,
+    ) -> Vec<usize> {
+        let mut alignment = 0;
+
+        let offsets = data
+            .into_iter()
+            .map(|elem| {
+                let mut elem = self.matrix_order(elem.as_gpu_bytes(self.layout));
+
+                elem.align_to(self.layout.array_element_alignment(elem.alignment));
+                alignment = alignment.max(elem.alignment);
+
+                self.write_data(&elem);
+                self.bytes.len() - elem.bytes.len()
+            })
+            .collect::<Vec<_>>();
+
+        // the whole array is recorded as one field
+        if let Some(&offset) = offsets.first() {
+            self.record_offset(offset, self.bytes.len() - offset, alignment);
+        }
+
+        offsets
+    }
+
+    // writes the elements `stride` bytes apart, leaving the buffer untouched if the stride is
+    // smaller than the layout's array stride for any of them
+    fn write_array_with_stride<T: AsGpuBytes>(
+        &mut self,
+        data: impl IntoIterator<Item = T>,
+        stride: usize,
+    ) -> Result<(), GpuBytesError> {
+        let elems = data
+            .into_iter()
+            .map(|elem| self.matrix_order(elem.as_gpu_bytes(self.layout)))
+            .collect::<Vec<_>>();
+
+        for elem in elems.iter() {
+            let align = self.layout.array_element_alignment(elem.alignment.max(1));
+            let natural_stride = elem.bytes.len().next_multiple_of(align);
+
+            if stride < natural_stride || !stride.is_multiple_of(align) {
+                return Err(GpuBytesError::InvalidStride {
+                    stride,
+                    size: elem.bytes.len(),
+                    alignment: elem.alignment,
+                });
             }
+        }
+
+        // like in `write_array`, the array starts at the alignment of its elements
+        let max_align = elems.iter().map(|elem| elem.alignment).max().unwrap_or(0);
+        let align = self.layout.array_element_alignment(max_align.max(1));
+
+        self.alignment = self.alignment.max(align);
+        self.pad_to(align);
+
+        let offset = self.bytes.len();
 
-            self.write(&elem);
+        for elem in elems.iter() {
+            self.write_data(elem);
+            self.pad_to_len(self.bytes.len() - elem.bytes.len() + stride);
         }
 
-        self
+        // the whole array is recorded as one field
+        if !elems.is_empty() {
+            self.record_offset(offset, self.bytes.len() - offset, align);
+        }
+
+        Ok(())
     }
 
     pub fn align(&mut self) -> &mut Self {
@@ -66,437 +454,4374 @@ impl GpuBytes {
     }
 
     pub fn align_to(&mut self, align: usize) -> &mut Self {
-        let offset = self.bytes.len();
-        let padding = (align - (offset % align)) % align;
+        self.last_align = Some((self.bytes.len(), self.alignment));
 
-        self.bytes.extend(std::iter::repeat(0u8).take(padding));
+        self.pad_to(align);
         self.alignment = align;
         self
     }
 
+    pub fn compact(&mut self) -> &mut Self {
+        if self.bytes.len() > self.data_len {
+            self.matrix = None;
+        }
+
+        self.padding -= self.bytes.len() - self.data_len;
+        self.bytes.truncate(self.data_len);
+        self.truncate_records(self.data_len);
+        self.last_align = None;
+        self
+    }
+
+    pub fn rewind_padding(&mut self) -> &mut Self {
+        if let Some((len, alignment)) = self.last_align.take() {
+            self.padding -= self.bytes.len() - len;
+            self.bytes.truncate(len);
+            self.truncate_records(len);
+            self.alignment = alignment;
+            self.matrix = None;
+        }
+
+        self
+    }
+
     pub fn as_slice(&self) -> &[u8] {
         &self.bytes
     }
-}
 
-impl AsGpuBytes for GpuBytes {
-    fn as_gpu_bytes(&self) -> GpuBytes {
-        self.clone()
+    /// Moves the bytes out without copying them.
+    pub fn into_vec(self) -> Vec<u8> {
+        self.bytes
     }
-}
 
-pub trait AsStd140 {
-    fn as_std140(&self) -> Std140Bytes;
-}
+    fn expect_fields(&mut self, names: &[&str]) {
+        self.expected_fields = names.iter().map(|name| name.to_string()).collect();
+    }
 
-#[derive(Debug, Clone)]
-pub struct Std140Bytes {
-    gpu_bytes: GpuBytes,
-}
+    fn next_field(&mut self, name: &str) {
+        match self.expected_fields.pop_front() {
+            Some(expected) if expected == name => {}
+            Some(expected) => {
+                panic!("field `{name}` was written, but field `{expected}` was expected next")
+            }
+            None => panic!("field `{name}` was written, but no more fields were expected"),
+        }
+    }
 
-impl Std140Bytes {
-    pub fn new() -> Self {
-        Self {
-            gpu_bytes: GpuBytes::new(Layout::Std140),
+    fn finish_fields(&mut self) {
+        if let Some(expected) = self.expected_fields.pop_front() {
+            panic!("field `{expected}` was never written");
         }
     }
 
-    pub fn write<T: AsStd140>(&mut self, data: &T) -> &mut Self {
-        self.gpu_bytes.write(&data.as_std140().gpu_bytes);
+    fn write_array_batch(&mut self, arrays: Vec<Vec<GpuBytes>>) -> &mut Self {
+        let mut count = SizeCount {
+            len: self.bytes.len(),
+            alignment: self.alignment,
+            layout: self.layout,
+        };
+
+        for elem in arrays.iter().flatten() {
+            count.write_element(elem);
+        }
+
+        self.bytes.reserve(count.len - self.bytes.len());
+
+        for array in arrays {
+            self.write_array(array);
+        }
+
         self
     }
 
-    pub fn write_array<T: AsStd140>(&mut self, data: &[T]) -> &mut Self {
-        self.gpu_bytes
-            .write_array(data.iter().map(|e| e.as_std140().gpu_bytes));
+    /// Clears the buffer and switches it to `layout`, keeping the allocation for reuse.
+    pub fn reset(&mut self, layout: Layout) -> &mut Self {
+        let mut bytes = std::mem::take(&mut self.bytes);
+        bytes.clear();
+
+        *self = Self {
+            bytes,
+            ..Self::new(layout)
+        };
         self
     }
 
-    pub fn align(&mut self) -> &mut Self {
-        self.gpu_bytes.align();
+    /// Clears the buffer, keeping its allocation, layout and settings such as transposed matrices.
+    pub fn clear(&mut self) -> &mut Self {
+        self.bytes.clear();
+        self.alignment = 0;
+        self.padding = 0;
+        self.data_len = 0;
+        self.expected_fields.clear();
+        self.last_align = None;
+        self.matrix = None;
+
+        if let Some(warnings) = self.padding_warnings.as_mut() {
+            warnings.clear();
+        }
+
+        if let Some(offsets) = self.debug_offsets.as_mut() {
+            offsets.clear();
+        }
+
         self
     }
 
-    pub fn align_to(&mut self, align: usize) -> &mut Self {
-        let offset = self.gpu_bytes.bytes.len();
-        let padding = (align - (offset % align)) % align;
+    /// Inserts `data` at `offset`, shifting everything after it. Any offsets recorded past `offset`
+    /// are invalidated, and nothing is realigned.
+    ///
+    /// Panics if `offset` is past the end of the buffer.
+    pub fn insert_at(&mut self, offset: usize, data: &[u8]) -> &mut Self {
+        self.bytes.splice(offset..offset, data.iter().copied());
 
-        self.gpu_bytes
-            .bytes
-            .extend(std::iter::repeat(0u8).take(padding));
-        self.gpu_bytes.alignment = align;
+        self.data_len = self.data_len.max(offset) + data.len();
+        self.last_align = None;
+        self.matrix = None;
         self
     }
 
-    pub fn as_slice(&self) -> &[u8] {
-        &self.gpu_bytes.bytes
+    // a buffer with the same size and alignment, consisting only of padding
+    fn zeroed(&self) -> GpuBytes {
+        GpuBytes {
+            bytes: vec![0; self.bytes.len()],
+            alignment: self.alignment,
+            layout: self.layout,
+            padding: self.bytes.len(),
+            ..Default::default()
+        }
     }
-}
 
-impl Default for Std140Bytes {
-    fn default() -> Self {
-        Self::new()
+    /// Like [`Self::align_to`], but leaves the buffer untouched and errors if aligning would grow
+    /// it past `max_size` bytes.
+    pub fn align_to_within(&mut self, align: usize, max_size: usize) -> Result<(), GpuBytesError> {
+        if !align.is_power_of_two() {
+            return Err(GpuBytesError::InvalidAlignment(align));
+        }
+
+        let size = self.bytes.len().next_multiple_of(align);
+
+        if size > max_size {
+            return Err(GpuBytesError::ExceedsMaxSize { size, max_size });
+        }
+
+        self.align_to(align);
+        Ok(())
     }
-}
 
-impl AsStd140 for Std140Bytes {
-    fn as_std140(&self) -> Std140Bytes {
-        self.clone()
+    pub fn layout(&self) -> Layout {
+        self.layout
     }
-}
 
-pub trait AsStd430 {
-    fn as_std430(&self) -> Std430Bytes;
-}
+    fn append_all<'a>(
+        &mut self,
+        others: impl IntoIterator<Item = &'a GpuBytes> + Clone,
+    ) -> &mut Self {
+        let mut count = SizeCount {
+            len: self.bytes.len(),
+            alignment: self.alignment,
+            layout: self.layout,
+        };
 
-#[derive(Debug, Clone)]
-pub struct Std430Bytes {
-    gpu_bytes: GpuBytes,
-}
+        for other in others.clone() {
+            count.write(other.bytes.len(), other.alignment);
+        }
 
-impl Std430Bytes {
-    pub fn new() -> Self {
-        Self {
-            gpu_bytes: GpuBytes::new(Layout::Std430),
+        self.bytes.reserve(count.len - self.bytes.len());
+
+        for other in others {
+            self.write_data(other);
         }
-    }
 
-    pub fn write<T: AsStd430>(&mut self, data: &T) -> &mut Self {
-        self.gpu_bytes.write(&data.as_std430().gpu_bytes);
         self
     }
 
-    pub fn write_array<T: AsStd430>(&mut self, data: &[T]) -> &mut Self {
-        self.gpu_bytes
-            .write_array(data.iter().map(|e| e.as_std430().gpu_bytes));
-        self
+    pub fn snapshot(&self) -> BuilderSnapshot {
+        BuilderSnapshot {
+            len: self.bytes.len(),
+            alignment: self.alignment,
+            padding: self.padding,
+            data_len: self.data_len,
+        }
     }
 
-    pub fn align(&mut self) -> &mut Self {
-        self.gpu_bytes.align();
+    /// Discards everything written since `snapshot` was taken.
+    ///
+    /// Panics if the buffer has become shorter than the snapshot.
+    pub fn restore(&mut self, snapshot: BuilderSnapshot) -> &mut Self {
+        assert!(
+            snapshot.len <= self.bytes.len(),
+            "cannot restore a snapshot of {} bytes into a buffer of {} bytes",
+            snapshot.len,
+            self.bytes.len()
+        );
+
+        self.bytes.truncate(snapshot.len);
+        self.truncate_records(snapshot.len);
+        self.alignment = snapshot.alignment;
+        self.padding = snapshot.padding;
+        self.data_len = snapshot.data_len;
+        self.last_align = None;
+        self.matrix = None;
         self
     }
 
-    pub fn align_to(&mut self, align: usize) -> &mut Self {
-        let offset = self.gpu_bytes.bytes.len();
-        let padding = (align - (offset % align)) % align;
-
-        self.gpu_bytes
-            .bytes
-            .extend(std::iter::repeat(0u8).take(padding));
-        self.gpu_bytes.alignment = align;
-        self
+    /// The largest alignment of anything written so far, which the buffer will be aligned to by
+    /// `align`. This is 0 if nothing has been written.
+    pub fn alignment(&self) -> usize {
+        self.alignment
     }
 
-    pub fn as_slice(&self) -> &[u8] {
-        &self.gpu_bytes.bytes
+    pub fn len(&self) -> usize {
+        self.bytes.len()
     }
-}
 
-impl Default for Std430Bytes {
-    fn default() -> Self {
-        Self::new()
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
     }
-}
 
-macro_rules! primitive_impl_std140_std430 {
-    ($datatype:ty, align = $align:literal) => {
-        impl AsStd140 for $datatype {
-            fn as_std140(&self) -> Std140Bytes {
-                let mut buf = Std140Bytes::new();
+    /// Returns the bytes if there are exactly `expected_len` of them, e.g. the size of the
+    /// struct declared in the shader.
+    pub fn finish_expecting(&self, expected_len: usize) -> Result<&[u8], GpuBytesError> {
+        if self.bytes.len() != expected_len {
+            return Err(GpuBytesError::SizeMismatch {
+                expected: expected_len,
+                actual: self.bytes.len(),
+            });
+        }
 
-                const SIZE: usize = std::mem::size_of::<$datatype>();
-                let cast: [u8; SIZE] = bytemuck::cast(*self);
+        Ok(&self.bytes)
+    }
 
-                buf.gpu_bytes.bytes.extend_from_slice(&cast);
-                buf.gpu_bytes.alignment = $align;
+    pub fn reserve_size_header(&mut self) -> SizeHeaderHandle {
+        self.write(&0u32);
 
-                buf
-            }
+        SizeHeaderHandle {
+            offset: self.bytes.len() - 4,
         }
+    }
 
-        impl AsStd430 for $datatype {
-            fn as_std430(&self) -> Std430Bytes {
-                let mut buf = Std430Bytes::new();
+    /// Errors without touching the header if the buffer is too long for its size to fit in it.
+    pub fn finalize_size_header(&mut self, handle: SizeHeaderHandle) -> Result<(), GpuBytesError> {
+        let size = u32::try_from(self.bytes.len()).map_err(|_| GpuBytesError::ExceedsMaxSize {
+            size: self.bytes.len(),
+            max_size: u32::MAX as usize,
+        })?;
 
-                const SIZE: usize = std::mem::size_of::<$datatype>();
-                let cast: [u8; SIZE] = bytemuck::cast(*self);
+        self.bytes[handle.offset..handle.offset + 4].copy_from_slice(&size.to_le_bytes());
+        Ok(())
+    }
+}
 
-                buf.gpu_bytes.bytes.extend_from_slice(&cast);
-                buf.gpu_bytes.alignment = $align;
+// already laid out bytes are written as they are, whatever the layout of the destination
+impl AsGpuBytes for GpuBytes {
+    fn as_gpu_bytes(&self, _layout: Layout) -> GpuBytes {
+        self.clone()
+    }
+}
 
-                buf
-            }
+impl<T: AsStd140 + AsStd430> AsGpuBytes for T {
+    fn as_gpu_bytes(&self, layout: Layout) -> GpuBytes {
+        match layout {
+            Layout::Std140 => self.as_std140().gpu_bytes,
+            Layout::Std430 => self.as_std430().gpu_bytes,
+            Layout::Metal => panic!("values can only be written in the Metal layout by MetalBytes"),
         }
-    };
+    }
 }
 
-macro_rules! primitive_impl_std140_std430_matrix {
-    ($datatype:ty, columns = $columns:literal) => {
-        impl AsStd140 for $datatype {
-            fn as_std140(&self) -> Std140Bytes {
-                let mut buf = Std140Bytes::new();
+// interleaves the bits of the three components, most significant bit first, so that sorting by the
+// key keeps spatially close cells close together in memory
+fn morton_key(cell: glam::IVec3) -> u128 {
+    // flipping the sign bit maps i32 to u32 while preserving order
+    let [x, y, z] = cell.to_array().map(|c| (c as u32 ^ (1 << 31)) as u128);
 
-                for i in 0..$columns {
-                    buf.write(&self.col(i));
-                }
+    let mut key = 0u128;
 
-                buf
-            }
-        }
+    for bit in (0..32).rev() {
+        key = (key << 3) | (((x >> bit) & 1) << 2) | (((y >> bit) & 1) << 1) | ((z >> bit) & 1);
+    }
 
-        impl AsStd430 for $datatype {
-            fn as_std430(&self) -> Std430Bytes {
-                let mut buf = Std430Bytes::new();
+    key
+}
 
-                for i in 0..$columns {
-                    buf.write(&self.col(i));
+// the methods of the builder for each layout, which only differ in the layout and in the trait that
+// written values implement
+macro_rules! layout_bytes_impl {
+    ($bytes:ident, $as_trait:ident, $as_method:ident, $layout:ident, $layout_name:literal) => {
+        impl $bytes {
+            pub fn new() -> Self {
+                Self {
+                    gpu_bytes: GpuBytes::new(Layout::$layout),
                 }
+            }
 
+            /// Creates a builder that writes matrices in row major order, i.e. transposed. This
+            /// only applies to matrices written directly to this builder, not to those nested
+            /// inside other values.
+            pub fn with_transposed_matrices(transpose: bool) -> Self {
+                let mut buf = Self::new();
+                buf.gpu_bytes.transpose_matrices = transpose;
                 buf
             }
-        }
-    };
-}
 
-primitive_impl_std140_std430!(f32, align = 4);
-primitive_impl_std140_std430!(glam::Vec2, align = 8);
+            /// Creates a builder that records a [`PaddingWarning`] whenever `write` has to insert
+            /// padding in front of a value. The warnings can be retrieved with
+            /// [`Self::padding_warnings`].
+            pub fn with_padding_warnings(warn: bool) -> Self {
+                let mut buf = Self::new();
+                buf.gpu_bytes.padding_warnings = warn.then(Vec::new);
+                buf
+            }
+
+            /// Creates a builder that records where every value passed to `write` ended up, for
+            /// comparing against the shader's reflection. The offsets can be retrieved with
+            /// [`Self::offsets`].
+            pub fn with_debug_offsets() -> Self {
+                let mut buf = Self::new();
+                buf.gpu_bytes.debug_offsets = Some(Vec::new());
+                buf
+            }
+
+            #[doc = concat!("Wraps bytes laid out in ", $layout_name, ", e.g. read from disk.")]
+            pub fn from_bytes(bytes: Vec<u8>, alignment: usize) -> Self {
+                Self {
+                    gpu_bytes: GpuBytes::from_bytes(Layout::$layout, bytes, alignment),
+                }
+            }
+
+            /// Like [`Self::from_bytes`], but errors if `alignment` is not a power of two, or if the
+            /// length of `bytes` is not a multiple of it.
+            pub fn from_bytes_checked(
+                bytes: Vec<u8>,
+                alignment: usize,
+            ) -> Result<Self, GpuBytesError> {
+                Ok(Self {
+                    gpu_bytes: GpuBytes::from_bytes_checked(Layout::$layout, bytes, alignment)?,
+                })
+            }
+
+            pub fn write<T: $as_trait>(&mut self, data: &T) -> &mut Self {
+                self.gpu_bytes.write(&data.$as_method().gpu_bytes);
+                self
+            }
+
+            /// Writes `data` as an array. The elements are assumed to be of the same type: each one
+            /// is padded according to its own alignment, so elements with differing alignments
+            /// won't share a common stride.
+            pub fn write_array<T: $as_trait>(&mut self, data: &[T]) -> &mut Self {
+                self.gpu_bytes
+                    .write_array(data.iter().map(|e| e.$as_method().gpu_bytes));
+                self
+            }
+
+            pub fn align(&mut self) -> &mut Self {
+                self.gpu_bytes.align();
+                self
+            }
+
+            pub fn align_to(&mut self, align: usize) -> &mut Self {
+                self.gpu_bytes.align_to(align);
+                self
+            }
+
+            pub fn as_slice(&self) -> &[u8] {
+                self.gpu_bytes.as_slice()
+            }
+
+            /// Moves the bytes out without copying them.
+            pub fn into_vec(self) -> Vec<u8> {
+                self.gpu_bytes.into_vec()
+            }
+
+            /// Writes `{ vec3<i32>, u32 }` pairs sorted by the morton order of their cell. Each
+            /// pair occupies one 16 byte slot, except in Metal, where the `vec3` alone takes up 16
+            /// bytes.
+            pub fn write_spatial_pairs(&mut self, pairs: &[(glam::IVec3, u32)]) -> &mut Self {
+                let mut pairs = pairs.to_vec();
+                pairs.sort_by_key(|(cell, _)| morton_key(*cell));
+
+                for (cell, index) in pairs.iter() {
+                    self.write(cell);
+                    self.write(index);
+                }
+
+                self
+            }
+
+            /// Writes a tangent with its bitangent handedness sign in `w`, as a single `vec4`.
+            pub fn write_tangent(&mut self, tangent: glam::Vec3, handedness: f32) -> &mut Self {
+                self.write(&tangent.extend(handedness))
+            }
+
+            /// Writes the values of `map` as an array in key order, so the output is reproducible.
+            pub fn write_sorted_map<K: Ord, V: $as_trait>(
+                &mut self,
+                map: &std::collections::BTreeMap<K, V>,
+            ) -> &mut Self {
+                self.gpu_bytes
+                    .write_array(map.values().map(|e| e.$as_method().gpu_bytes));
+                self
+            }
+
+            /// The number of bytes in the buffer that are padding rather than data.
+            pub fn padding_bytes(&self) -> usize {
+                self.gpu_bytes.padding
+            }
+
+            /// Writes a rectangle as a single `vec4`, with `min` in `xy` and `max` in `zw`.
+            pub fn write_rect(&mut self, min: glam::Vec2, max: glam::Vec2) -> &mut Self {
+                self.write(&glam::Vec4::new(min.x, min.y, max.x, max.y))
+            }
+
+            /// Writes `data` as an array of `vec4<f32>` in a single copy, since a `vec4` array has
+            /// no padding between elements.
+            pub fn write_vec4_array_from_f32(&mut self, data: &[[f32; 4]]) -> &mut Self {
+                if !data.is_empty() {
+                    let bytes = bytemuck::cast_slice(data);
+                    write_scalar_bytes_le(&mut self.gpu_bytes, bytes, 4, 16);
+
+                    let offset = self.gpu_bytes.bytes.len() - bytes.len();
+                    self.gpu_bytes.record_offset(offset, bytes.len(), 16);
+                }
+
+                self
+            }
+
+            /// Truncates the padding after the last byte of data, e.g. the padding inserted by a
+            /// final `align`. Only use this when the shader's declared size doesn't include that
+            /// padding.
+            pub fn compact(&mut self) -> &mut Self {
+                self.gpu_bytes.compact();
+                self
+            }
+
+            /// Declares the fields that will be written with [`Self::write_field`], in order.
+            pub fn expect_fields(&mut self, names: &[&str]) -> &mut Self {
+                self.gpu_bytes.expect_fields(names);
+                self
+            }
+
+            /// Writes a field declared with [`Self::expect_fields`], panicking if it is written out
+            /// of order.
+            pub fn write_field<T: $as_trait>(&mut self, name: &str, data: &T) -> &mut Self {
+                self.gpu_bytes.next_field(name);
+                self.write(data)
+            }
+
+            /// Panics if any field declared with [`Self::expect_fields`] was skipped.
+            pub fn finish_fields(&mut self) -> &mut Self {
+                self.gpu_bytes.finish_fields();
+                self
+            }
+
+            /// Removes the padding inserted by the most recent `align` or `align_to`, as long as
+            /// nothing has been written since.
+            pub fn rewind_padding(&mut self) -> &mut Self {
+                self.gpu_bytes.rewind_padding();
+                self
+            }
+
+            /// Writes several arrays back to back, reserving space for all of them up front.
+            pub fn write_array_batch<T: $as_trait>(&mut self, arrays: &[&[T]]) -> &mut Self {
+                self.gpu_bytes.write_array_batch(
+                    arrays
+                        .iter()
+                        .map(|array| array.iter().map(|e| e.$as_method().gpu_bytes).collect())
+                        .collect(),
+                );
+                self
+            }
+
+            /// Writes a bounding sphere as a single `vec4`, with the radius in `w`.
+            pub fn write_sphere(&mut self, center: glam::Vec3, radius: f32) -> &mut Self {
+                self.write(&center.extend(radius))
+            }
+
+            /// Reads all bytes from `reader` into a new builder, like [`Self::from_bytes`].
+            pub fn from_reader(
+                reader: &mut impl std::io::Read,
+                alignment: usize,
+            ) -> std::io::Result<Self> {
+                let mut bytes = Vec::new();
+                reader.read_to_end(&mut bytes)?;
+
+                Ok(Self::from_bytes(bytes, alignment))
+            }
+
+            /// Writes `data` packed two per `vec4`, in `xy` and `zw`. A trailing odd element leaves
+            /// `zw` zeroed.
+            pub fn write_vec2_array_packed(&mut self, data: &[glam::Vec2]) -> &mut Self {
+                for pair in data.chunks(2) {
+                    let xy = pair[0];
+                    let zw = pair.get(1).copied().unwrap_or(glam::Vec2::ZERO);
+
+                    self.write(&glam::Vec4::new(xy.x, xy.y, zw.x, zw.y));
+                }
+
+                self
+            }
+
+            /// Like [`Self::align`], but returns the number of padding bytes that were inserted.
+            pub fn align_reporting(&mut self) -> usize {
+                let len = self.gpu_bytes.bytes.len();
+                self.gpu_bytes.align();
+                self.gpu_bytes.bytes.len() - len
+            }
+
+            /// Like [`Self::align_to`], but returns the number of padding bytes that were inserted.
+            pub fn align_to_reporting(&mut self, align: usize) -> usize {
+                let len = self.gpu_bytes.bytes.len();
+                self.gpu_bytes.align_to(align);
+                self.gpu_bytes.bytes.len() - len
+            }
+
+            /// Writes the unique elements of `data` as an array, in order of first appearance, and
+            /// returns the index of each element of `data` into that array.
+            pub fn write_deduplicated<T: $as_trait + Eq + std::hash::Hash>(
+                &mut self,
+                data: &[T],
+            ) -> Vec<u32> {
+                let mut unique = Vec::new();
+                let mut indices = std::collections::HashMap::new();
+
+                let indices = data
+                    .iter()
+                    .map(|elem| {
+                        *indices.entry(elem).or_insert_with(|| {
+                            unique.push(elem);
+                            unique.len() as u32 - 1
+                        })
+                    })
+                    .collect();
+
+                self.gpu_bytes
+                    .write_array(unique.iter().map(|e| e.$as_method().gpu_bytes));
+
+                indices
+            }
+
+            /// Writes a point light as two `vec4`s: the position with the range in `w`, then the
+            /// color with the intensity in `w`.
+            pub fn write_point_light(
+                &mut self,
+                position: glam::Vec3,
+                range: f32,
+                color: glam::Vec3,
+                intensity: f32,
+            ) -> &mut Self {
+                self.write(&position.extend(range))
+                    .write(&color.extend(intensity))
+            }
+
+            /// Inserts `data` at `offset`, shifting everything after it. Any offsets recorded past
+            /// `offset` are invalidated, and nothing is realigned.
+            ///
+            /// Panics if `offset` is past the end of the buffer.
+            pub fn insert_at(&mut self, offset: usize, data: &[u8]) -> &mut Self {
+                self.gpu_bytes.insert_at(offset, data);
+                self
+            }
+
+            /// Writes an affine transform compactly as a `mat3x4<f32>`, where column `i` holds row
+            /// `i` of the linear part with the `i`th component of the translation in `w`. In the
+            /// shader, a point is transformed with `vec4(p, 1.0) * m`.
+            pub fn write_affine3a_compact(&mut self, affine: glam::Affine3A) -> &mut Self {
+                for i in 0..3 {
+                    let row = glam::Vec3::from(affine.matrix3.row(i));
+
+                    self.write(&row.extend(affine.translation[i]));
+                }
+
+                self
+            }
+
+            /// Writes at most `max` elements of `data` as an array, padding the remaining slots
+            /// with zeros so the array always holds `max` elements.
+            pub fn write_array_capped<T: $as_trait + Default>(
+                &mut self,
+                data: impl IntoIterator<Item = T>,
+                max: usize,
+            ) -> &mut Self {
+                let mut written = 0;
+
+                self.gpu_bytes
+                    .write_array(data.into_iter().take(max).map(|e| {
+                        written += 1;
+                        e.$as_method().gpu_bytes
+                    }));
+
+                let empty = T::default().$as_method().gpu_bytes.zeroed();
+                self.gpu_bytes
+                    .write_array(std::iter::repeat_n(empty, max - written));
+
+                self
+            }
+
+            /// Like [`Self::align_to`], but leaves the buffer untouched and errors if aligning
+            /// would grow it past `max_size` bytes.
+            pub fn align_to_within(
+                &mut self,
+                align: usize,
+                max_size: usize,
+            ) -> Result<(), GpuBytesError> {
+                self.gpu_bytes.align_to_within(align, max_size)
+            }
+
+            /// Writes bytes that were already laid out, erroring if they were built for another
+            /// layout.
+            pub fn write_gpu_bytes(&mut self, other: &GpuBytes) -> Result<(), GpuBytesError> {
+                if other.layout != Layout::$layout {
+                    return Err(GpuBytesError::LayoutMismatch {
+                        expected: Layout::$layout,
+                        actual: other.layout,
+                    });
+                }
+
+                self.gpu_bytes.write(other);
+                Ok(())
+            }
+
+            /// Writes skinning data as a `vec4<u32>` of bone indices followed by a `vec4<f32>` of
+            /// weights.
+            pub fn write_bone_data(&mut self, indices: [u32; 4], weights: [f32; 4]) -> &mut Self {
+                self.write(&Vector4(indices)).write(&Vector4(weights))
+            }
+
+            /// Appends another buffer, first aligning this one to the other's alignment.
+            pub fn append(&mut self, other: &$bytes) -> &mut Self {
+                self.gpu_bytes.write_data(&other.gpu_bytes);
+                self
+            }
+
+            /// Appends each of `others` like [`Self::append`], reserving space for all of them up
+            /// front.
+            pub fn append_all(&mut self, others: &[$bytes]) -> &mut Self {
+                self.gpu_bytes
+                    .append_all(others.iter().map(|other| &other.gpu_bytes));
+                self
+            }
+
+            pub fn snapshot(&self) -> BuilderSnapshot {
+                self.gpu_bytes.snapshot()
+            }
+
+            /// Discards everything written since `snapshot` was taken.
+            ///
+            /// Panics if the buffer has become shorter than the snapshot.
+            pub fn restore(&mut self, snapshot: BuilderSnapshot) -> &mut Self {
+                self.gpu_bytes.restore(snapshot);
+                self
+            }
+
+            /// Writes a viewport or scissor rect as a single `vec4<u32>` of `(x, y, width,
+            /// height)`.
+            pub fn write_viewport(&mut self, x: u32, y: u32, width: u32, height: u32) -> &mut Self {
+                self.write(&glam::UVec4::new(x, y, width, height))
+            }
+
+            /// Like [`Self::write_array`], but returns the offset of each element, e.g. for
+            /// patching it later.
+            pub fn write_array_offsets<T: $as_trait>(&mut self, data: &[T]) -> Vec<usize> {
+                self.gpu_bytes
+                    .write_array_offsets(data.iter().map(|e| e.$as_method().gpu_bytes))
+            }
+
+            pub fn padding_warnings(&self) -> &[PaddingWarning] {
+                self.gpu_bytes
+                    .padding_warnings
+                    .as_deref()
+                    .unwrap_or_default()
+            }
+
+            /// Removes and returns the warnings recorded so far, so that a long-lived builder
+            /// doesn't accumulate them. Warnings keep being recorded afterwards.
+            pub fn take_padding_warnings(&mut self) -> Vec<PaddingWarning> {
+                self.gpu_bytes
+                    .padding_warnings
+                    .as_mut()
+                    .map(std::mem::take)
+                    .unwrap_or_default()
+            }
+
+            /// Like [`Self::write`], for heterogeneous values stored as trait objects.
+            pub fn write_dyn(&mut self, data: &dyn $as_trait) -> &mut Self {
+                self.gpu_bytes.write(&data.$as_method().gpu_bytes);
+                self
+            }
+
+            /// Writes a decomposed transform as three `vec4<f32>`s: the rotation quaternion as
+            /// `xyzw`, then the translation and the scale, each with an unused `w` of zero.
+            pub fn write_decomposed_transform(
+                &mut self,
+                translation: glam::Vec3,
+                rotation: glam::Quat,
+                scale: glam::Vec3,
+            ) -> &mut Self {
+                self.write(&rotation)
+                    .write(&translation.extend(0.0))
+                    .write(&scale.extend(0.0))
+            }
+
+            /// The largest alignment of anything written so far, which the buffer will be aligned
+            /// to by `align`. This is 0 if nothing has been written.
+            pub fn alignment(&self) -> usize {
+                self.gpu_bytes.alignment()
+            }
+
+            pub fn len(&self) -> usize {
+                self.gpu_bytes.len()
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.gpu_bytes.is_empty()
+            }
+
+            /// Writes `data` as a struct occupying at least `min_size` bytes, for shader structs
+            /// declared with trailing members that are left zeroed. The slot is still rounded up to
+            /// the alignment of the struct.
+            pub fn write_struct_sized<T: $as_trait>(
+                &mut self,
+                data: &T,
+                min_size: usize,
+            ) -> &mut Self {
+                // pick the matrix order first, so that the padding applies to the bytes actually
+                // written
+                let mut data = self.gpu_bytes.matrix_order(data.$as_method().gpu_bytes);
+
+                data.pad_to_len(min_size);
+                data.align();
+
+                self.gpu_bytes.write(&data);
+                self
+            }
+
+            /// Writes a 2D rotation and translation as a `vec4<f32>` of `(cos, sin, tx, ty)`, with
+            /// the rotation in radians.
+            pub fn write_transform2d(
+                &mut self,
+                rotation: f32,
+                translation: glam::Vec2,
+            ) -> &mut Self {
+                let (sin, cos) = rotation.sin_cos();
+
+                self.write(&glam::Vec4::new(cos, sin, translation.x, translation.y))
+            }
+
+            /// Like [`Self::write_array`], but with each element padded to `stride` bytes instead
+            /// of the layout's array stride, like an explicit stride in the shader. Errors without
+            /// writing anything if `stride` is smaller than the natural array stride of an element,
+            /// or isn't a multiple of its array alignment.
+            pub fn write_array_with_stride<T: $as_trait>(
+                &mut self,
+                data: &[T],
+                stride: usize,
+            ) -> Result<(), GpuBytesError> {
+                self.gpu_bytes
+                    .write_array_with_stride(data.iter().map(|e| e.$as_method().gpu_bytes), stride)
+            }
+
+            /// Writes the lists flattened into one array, returning the `(offset, count)` of each
+            /// list in elements, so that list `i` is `array[offset..offset + count]` in the shader.
+            pub fn write_ragged<T: $as_trait>(&mut self, data: &[Vec<T>]) -> Vec<(u32, u32)> {
+                let mut offset = 0;
+                let table = data
+                    .iter()
+                    .map(|list| {
+                        let entry = (offset, list.len() as u32);
+                        offset += list.len() as u32;
+
+                        entry
+                    })
+                    .collect();
+
+                self.gpu_bytes
+                    .write_array(data.iter().flatten().map(|e| e.$as_method().gpu_bytes));
+
+                table
+            }
+
+            /// Clears the buffer, keeping its allocation and settings, to refill it e.g. every
+            /// frame.
+            pub fn clear(&mut self) -> &mut Self {
+                self.gpu_bytes.clear();
+                self
+            }
+
+            /// Writes `data` if it's `Ok`, otherwise returns the error without writing anything, so
+            /// that fallible values can be chained with `?`.
+            pub fn write_try<T: $as_trait, E>(
+                &mut self,
+                data: Result<&T, E>,
+            ) -> Result<&mut Self, E> {
+                Ok(self.write(data?))
+            }
+
+            /// Writes PBR material parameters as a `vec4<f32>` base color with alpha in `w`,
+            /// followed by a `vec4<f32>` of `(metallic, roughness, ao, emissive)`.
+            pub fn write_pbr_params(
+                &mut self,
+                base_color: glam::Vec4,
+                metallic: f32,
+                roughness: f32,
+                ao: f32,
+                emissive: f32,
+            ) -> &mut Self {
+                self.write(&base_color)
+                    .write(&glam::Vec4::new(metallic, roughness, ao, emissive))
+            }
+
+            /// Like [`Self::write_array`], but takes the elements from an iterator, so they don't
+            /// have to be collected first.
+            pub fn write_array_iter<T: $as_trait>(
+                &mut self,
+                data: impl IntoIterator<Item = T>,
+            ) -> &mut Self {
+                self.gpu_bytes
+                    .write_array(data.into_iter().map(|e| e.$as_method().gpu_bytes));
+                self
+            }
+
+            /// Writes a complex number as a `vec2<f32>` of `(re, im)`.
+            pub fn write_complex(&mut self, re: f32, im: f32) -> &mut Self {
+                self.write(&Complex::new(re, im))
+            }
+
+            /// Writes a duration without losing precision, as a `vec2<u32>` of the whole seconds,
+            /// truncated to 32 bits, and the nanoseconds past them.
+            pub fn write_duration_precise(&mut self, duration: std::time::Duration) -> &mut Self {
+                self.write(&glam::UVec2::new(
+                    duration.as_secs() as u32,
+                    duration.subsec_nanos(),
+                ))
+            }
+
+            /// Returns the bytes if there are exactly `expected_len` of them, e.g. the size of the
+            /// struct declared in the shader, turning a layout that drifted from the shader into an
+            /// error.
+            pub fn finish_expecting(&self, expected_len: usize) -> Result<&[u8], GpuBytesError> {
+                self.gpu_bytes.finish_expecting(expected_len)
+            }
+
+            /// The offsets recorded by a builder created with [`Self::with_debug_offsets`], or
+            /// nothing for any other builder.
+            pub fn offsets(&self) -> &[FieldOffset] {
+                self.gpu_bytes.debug_offsets.as_deref().unwrap_or_default()
+            }
+
+            /// Writes `data` as an array followed by its length as a `u32`, for shaders that expect
+            /// the count after the elements.
+            pub fn write_array_with_footer<T: $as_trait>(&mut self, data: &[T]) -> &mut Self {
+                self.write_array(data).write(&(data.len() as u32))
+            }
+
+            /// Writes the number of active clip planes as a `u32`, followed by an array of `max`
+            /// planes in which the unused slots are zeroed. Planes past `max` are dropped.
+            pub fn write_clip_planes(&mut self, planes: &[glam::Vec4], max: usize) -> &mut Self {
+                self.write(&(planes.len().min(max) as u32))
+                    .write_array_capped(planes.iter().copied(), max)
+            }
+
+            /// Writes a placeholder `u32` for the size of the buffer, to be filled in by
+            /// [`Self::finalize_size_header`] once everything else is written.
+            pub fn reserve_size_header(&mut self) -> SizeHeaderHandle {
+                self.gpu_bytes.reserve_size_header()
+            }
+
+            /// Fills in a size header with the current length of the buffer, so any final `align`
+            /// should come first. Errors if the length doesn't fit in the `u32` header.
+            pub fn finalize_size_header(
+                &mut self,
+                handle: SizeHeaderHandle,
+            ) -> Result<(), GpuBytesError> {
+                self.gpu_bytes.finalize_size_header(handle)
+            }
+
+            /// Like [`Self::write`], but takes `data` by value, e.g. for temporaries like `a + b`.
+            pub fn write_value<T: $as_trait>(&mut self, data: T) -> &mut Self {
+                self.write(&data)
+            }
+
+            /// Writes `data` tightly packed at a 12-byte stride and 4-byte alignment, to be read in
+            /// the shader as an `array<f32>` with three floats per vector. Unlike
+            /// [`Self::write_array`], this breaks the layout's rules for an array of `vec3<f32>`s,
+            /// so the shader must not declare it as one; this relies on driver behavior and may not
+            /// work everywhere.
+            pub fn write_vec3_array_tight(&mut self, data: &[glam::Vec3]) -> &mut Self {
+                for v in data {
+                    write_scalar_le(&mut self.gpu_bytes, v, 4, 4);
+                }
+
+                self
+            }
+
+            /// Writes an array of `{ a[i], b[i] }` structs, e.g. interleaving vertex positions and
+            /// normals. Errors without writing anything if `a` and `b` have different lengths.
+            pub fn write_interleaved<A: $as_trait, B: $as_trait>(
+                &mut self,
+                a: &[A],
+                b: &[B],
+            ) -> Result<(), GpuBytesError> {
+                if a.len() != b.len() {
+                    return Err(GpuBytesError::LengthMismatch {
+                        left: a.len(),
+                        right: b.len(),
+                    });
+                }
+
+                self.gpu_bytes.write_array(a.iter().zip(b).map(|(a, b)| {
+                    let mut elem = Self::new();
+                    elem.write(a).write(b).align();
+
+                    elem.gpu_bytes
+                }));
+
+                Ok(())
+            }
+        }
+    };
+}
+
+pub trait AsStd140 {
+    fn as_std140(&self) -> Std140Bytes;
+
+    /// A fallible version of [`AsStd140::as_std140`], for types that can't always be
+    /// converted, such as a `Vec<T>` without capacity.
+    fn try_as_std140(&self) -> Result<Std140Bytes, GpuBytesError> {
+        Ok(self.as_std140())
+    }
+
+    /// How a `[Self; N]` is written, which is an array of `Self` unless a type packs its arrays
+    /// differently, like `bool`.
+    fn array_as_std140<const N: usize>(array: &[Self; N]) -> Std140Bytes
+    where
+        Self: Sized,
+    {
+        array.as_slice().as_std140()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Std140Bytes {
+    gpu_bytes: GpuBytes,
+}
+
+layout_bytes_impl!(Std140Bytes, AsStd140, as_std140, Std140, "std140");
+
+impl Default for Std140Bytes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AsStd140 for Std140Bytes {
+    fn as_std140(&self) -> Std140Bytes {
+        self.clone()
+    }
+}
+
+pub trait AsStd430 {
+    fn as_std430(&self) -> Std430Bytes;
+
+    /// A fallible version of [`AsStd430::as_std430`], for types that can't always be
+    /// converted, such as a `Vec<T>` without capacity.
+    fn try_as_std430(&self) -> Result<Std430Bytes, GpuBytesError> {
+        Ok(self.as_std430())
+    }
+
+    /// How a `[Self; N]` is written, which is an array of `Self` unless a type packs its arrays
+    /// differently, like `bool`.
+    fn array_as_std430<const N: usize>(array: &[Self; N]) -> Std430Bytes
+    where
+        Self: Sized,
+    {
+        array.as_slice().as_std430()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Std430Bytes {
+    gpu_bytes: GpuBytes,
+}
+
+layout_bytes_impl!(Std430Bytes, AsStd430, as_std430, Std430, "std430");
+
+impl Default for Std430Bytes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AsStd430 for Std430Bytes {
+    fn as_std430(&self) -> Std430Bytes {
+        self.clone()
+    }
+}
+
+pub trait AsMetal {
+    fn as_metal(&self) -> MetalBytes;
+
+    /// A fallible version of [`AsMetal::as_metal`], for types that can't always be
+    /// converted, such as a `Vec<T>` without capacity.
+    fn try_as_metal(&self) -> Result<MetalBytes, GpuBytesError> {
+        Ok(self.as_metal())
+    }
+
+    /// How a `[Self; N]` is written, which is an array of `Self` unless a type packs its arrays
+    /// differently, like `bool`.
+    fn array_as_metal<const N: usize>(array: &[Self; N]) -> MetalBytes
+    where
+        Self: Sized,
+    {
+        array.as_slice().as_metal()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MetalBytes {
+    gpu_bytes: GpuBytes,
+}
+
+layout_bytes_impl!(MetalBytes, AsMetal, as_metal, Metal, "the Metal layout");
+
+impl Default for MetalBytes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AsMetal for MetalBytes {
+    fn as_metal(&self) -> MetalBytes {
+        self.clone()
+    }
+}
+
+/// Either the std140 or the std430 builder, chosen at runtime by its [`Layout`]. Metal isn't
+/// included, so that values only need [`AsStd140`] and [`AsStd430`] to be written.
+#[derive(Debug, Clone)]
+pub enum GpuBuffer {
+    Std140(Std140Bytes),
+    Std430(Std430Bytes),
+}
+
+impl GpuBuffer {
+    /// # Panics
+    ///
+    /// Panics if `layout` is [`Layout::Metal`], which is written with [`MetalBytes`] instead.
+    pub fn new(layout: Layout) -> Self {
+        match layout {
+            Layout::Std140 => GpuBuffer::Std140(Std140Bytes::new()),
+            Layout::Std430 => GpuBuffer::Std430(Std430Bytes::new()),
+            Layout::Metal => panic!("a GpuBuffer can't use the Metal layout, use MetalBytes"),
+        }
+    }
+
+    pub fn layout(&self) -> Layout {
+        match self {
+            GpuBuffer::Std140(_) => Layout::Std140,
+            GpuBuffer::Std430(_) => Layout::Std430,
+        }
+    }
+
+    pub fn write<T: AsStd140 + AsStd430>(&mut self, data: &T) -> &mut Self {
+        match self {
+            GpuBuffer::Std140(buf) => {
+                buf.write(data);
+            }
+            GpuBuffer::Std430(buf) => {
+                buf.write(data);
+            }
+        }
+
+        self
+    }
+
+    pub fn write_array<T: AsStd140 + AsStd430>(&mut self, data: &[T]) -> &mut Self {
+        match self {
+            GpuBuffer::Std140(buf) => {
+                buf.write_array(data);
+            }
+            GpuBuffer::Std430(buf) => {
+                buf.write_array(data);
+            }
+        }
+
+        self
+    }
+
+    pub fn align(&mut self) -> &mut Self {
+        match self {
+            GpuBuffer::Std140(buf) => {
+                buf.align();
+            }
+            GpuBuffer::Std430(buf) => {
+                buf.align();
+            }
+        }
+
+        self
+    }
+
+    pub fn align_to(&mut self, align: usize) -> &mut Self {
+        match self {
+            GpuBuffer::Std140(buf) => {
+                buf.align_to(align);
+            }
+            GpuBuffer::Std430(buf) => {
+                buf.align_to(align);
+            }
+        }
+
+        self
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        match self {
+            GpuBuffer::Std140(buf) => buf.as_slice(),
+            GpuBuffer::Std430(buf) => buf.as_slice(),
+        }
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        match self {
+            GpuBuffer::Std140(buf) => buf.into_vec(),
+            GpuBuffer::Std430(buf) => buf.into_vec(),
+        }
+    }
+}
+
+// mirrors the offset and alignment bookkeeping of `GpuBytes` without storing any bytes
+#[derive(Debug, Default, Clone, Copy)]
+struct SizeCount {
+    len: usize,
+    alignment: usize,
+    layout: Layout,
+}
+
+impl SizeCount {
+    fn new(layout: Layout) -> Self {
+        Self {
+            layout,
+            ..Default::default()
+        }
+    }
+
+    fn write(&mut self, len: usize, align: usize) {
+        self.alignment = self.alignment.max(align);
+        self.len = self.len.next_multiple_of(align.max(1)) + len;
+    }
+
+    fn write_element(&mut self, elem: &GpuBytes) {
+        let align = self.layout.array_element_alignment(elem.alignment).max(1);
+
+        self.write(elem.bytes.len().next_multiple_of(align), align);
+    }
+
+    fn write_array(&mut self, elements: impl IntoIterator<Item = GpuBytes>) {
+        for elem in elements {
+            self.write_element(&elem);
+        }
+    }
+
+    fn align(&mut self) {
+        self.align_to(self.alignment);
+    }
+
+    fn align_to(&mut self, align: usize) {
+        self.len = self.len.next_multiple_of(align.max(1));
+        self.alignment = align;
+    }
+}
+
+/// Computes the length of a std140 buffer without building it, for pre-sizing allocations.
+#[derive(Debug, Clone, Copy)]
+pub struct Std140SizeCounter {
+    count: SizeCount,
+}
+
+impl Std140SizeCounter {
+    pub fn new() -> Self {
+        Self {
+            count: SizeCount::new(Layout::Std140),
+        }
+    }
+
+    pub fn write<T: AsStd140>(&mut self, data: &T) -> &mut Self {
+        let data = data.as_std140();
+
+        self.count
+            .write(data.gpu_bytes.bytes.len(), data.gpu_bytes.alignment);
+        self
+    }
+
+    pub fn write_array<T: AsStd140>(&mut self, data: &[T]) -> &mut Self {
+        self.count
+            .write_array(data.iter().map(|e| e.as_std140().gpu_bytes));
+        self
+    }
+
+    pub fn align(&mut self) -> &mut Self {
+        self.count.align();
+        self
+    }
+
+    pub fn align_to(&mut self, align: usize) -> &mut Self {
+        self.count.align_to(align);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.count.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count.len == 0
+    }
+}
+
+impl Default for Std140SizeCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Computes the length of a std430 buffer without building it, for pre-sizing allocations.
+#[derive(Debug, Clone, Copy)]
+pub struct Std430SizeCounter {
+    count: SizeCount,
+}
+
+impl Std430SizeCounter {
+    pub fn new() -> Self {
+        Self {
+            count: SizeCount::new(Layout::Std430),
+        }
+    }
+
+    pub fn write<T: AsStd430>(&mut self, data: &T) -> &mut Self {
+        let data = data.as_std430();
+
+        self.count
+            .write(data.gpu_bytes.bytes.len(), data.gpu_bytes.alignment);
+        self
+    }
+
+    pub fn write_array<T: AsStd430>(&mut self, data: &[T]) -> &mut Self {
+        self.count
+            .write_array(data.iter().map(|e| e.as_std430().gpu_bytes));
+        self
+    }
+
+    pub fn align(&mut self) -> &mut Self {
+        self.count.align();
+        self
+    }
+
+    pub fn align_to(&mut self, align: usize) -> &mut Self {
+        self.count.align_to(align);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.count.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count.len == 0
+    }
+}
+
+impl Default for Std430SizeCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// reverses the bytes of every `component` sized scalar in `bytes`, converting between byte orders
+fn swap_byte_order(bytes: &mut [u8], component: usize) {
+    for scalar in bytes.chunks_exact_mut(component) {
+        scalar.reverse();
+    }
+}
+
+// writes data made of `component` sized scalars in little endian, regardless of the host
+fn write_scalar_le<T: bytemuck::Pod>(buf: &mut GpuBytes, data: &T, component: usize, align: usize) {
+    write_scalar_bytes_le(buf, bytemuck::bytes_of(data), component, align);
+}
+
+// like `write_scalar_le`, but for the native bytes of any number of scalars
+fn write_scalar_bytes_le(buf: &mut GpuBytes, bytes: &[u8], component: usize, align: usize) {
+    if cfg!(target_endian = "little") {
+        buf.write_slice(bytes, align);
+    } else {
+        let mut bytes = bytes.to_vec();
+        swap_byte_order(&mut bytes, component);
+
+        buf.write_slice(&bytes, align);
+    }
+}
+
+// reads data made of `component` sized scalars in little endian, regardless of the host
+fn read_scalar_le<T: bytemuck::Pod>(bytes: &[u8], component: usize) -> Result<T, GpuBytesError> {
+    let needed = std::mem::size_of::<T>();
+    let bytes = bytes.get(..needed).ok_or(GpuBytesError::TooShort {
+        len: bytes.len(),
+        needed,
+    })?;
+
+    if cfg!(target_endian = "little") {
+        Ok(bytemuck::pod_read_unaligned(bytes))
+    } else {
+        let mut bytes = bytes.to_vec();
+        swap_byte_order(&mut bytes, component);
+
+        Ok(bytemuck::pod_read_unaligned(&bytes))
+    }
+}
+
+// the layout traits must stay object safe, so that values can be written through `write_dyn`
+const _: Option<(&dyn AsStd140, &dyn AsStd430, &dyn AsMetal)> = None;
+
+macro_rules! primitive_impl_std140_std430 {
+    ($datatype:ty, align = $align:literal) => {
+        primitive_impl_std140_std430!($datatype, align = $align, component = 4);
+    };
+    ($datatype:ty, align = $align:literal, component = $component:literal) => {
+        primitive_impl_std140_std430!(@std $datatype, align = $align, component = $component);
+
+        impl AsMetal for $datatype {
+            fn as_metal(&self) -> MetalBytes {
+                let mut buf = MetalBytes::new();
+
+                write_scalar_le(&mut buf.gpu_bytes, self, $component, $align);
+
+                // in metal, the size of a vector is always a multiple of its alignment, so a
+                // 3-component vector takes up as much space as a 4-component one
+                buf.align();
+
+                buf
+            }
+        }
+
+        impl ShaderSize for $datatype {
+            fn shader_size(layout: Layout) -> usize {
+                let size = std::mem::size_of::<$datatype>();
+
+                match layout {
+                    Layout::Std140 | Layout::Std430 => size,
+                    Layout::Metal => size.next_multiple_of($align),
+                }
+            }
+        }
+    };
+    // MSL has no 64-bit floats, so these types don't implement `AsMetal`, and their Metal
+    // alignment is never used
+    ($datatype:ty, align = $align:literal, component = $component:literal, no_metal) => {
+        primitive_impl_std140_std430!(@std $datatype, align = $align, component = $component);
+
+        impl ShaderSize for $datatype {
+            fn shader_size(layout: Layout) -> usize {
+                match layout {
+                    Layout::Std140 | Layout::Std430 => std::mem::size_of::<$datatype>(),
+                    Layout::Metal => panic!("{} has no Metal equivalent", stringify!($datatype)),
+                }
+            }
+        }
+    };
+    (@std $datatype:ty, align = $align:literal, component = $component:literal) => {
+        impl AsStd140 for $datatype {
+            fn as_std140(&self) -> Std140Bytes {
+                let mut buf = Std140Bytes::new();
+
+                write_scalar_le(&mut buf.gpu_bytes, self, $component, $align);
+
+                buf
+            }
+        }
+
+        impl AsStd430 for $datatype {
+            fn as_std430(&self) -> Std430Bytes {
+                let mut buf = Std430Bytes::new();
+
+                write_scalar_le(&mut buf.gpu_bytes, self, $component, $align);
+
+                buf
+            }
+        }
+
+        impl FromStd430 for $datatype {
+            fn from_std430(bytes: &[u8]) -> Result<Self, GpuBytesError> {
+                read_scalar_le(bytes, $component)
+            }
+        }
+
+        impl ShaderAlignment for $datatype {
+            const STD140_ALIGNMENT: usize = $align;
+            const STD430_ALIGNMENT: usize = $align;
+            const METAL_ALIGNMENT: usize = $align;
+        }
+    };
+}
+
+/// The number of bytes a type occupies in a given layout, including the padding between its
+/// columns, e.g. 48 for a `mat3x3<f32>` since each `vec3` column is padded to 16 bytes.
+pub trait ShaderSize {
+    fn shader_size(layout: Layout) -> usize;
+}
+
+/// The alignment of a type in each layout, known at compile time for types whose layout doesn't
+/// depend on their value, e.g. 16 for a `Vec3`. This is separate from [`AsStd140`] and the other
+/// layout traits, since associated constants would keep them from being object safe.
+pub trait ShaderAlignment {
+    const STD140_ALIGNMENT: usize;
+    const STD430_ALIGNMENT: usize;
+    const METAL_ALIGNMENT: usize;
+
+    /// The alignment in `layout`, for code that only knows the layout at runtime.
+    fn shader_alignment(layout: Layout) -> usize {
+        match layout {
+            Layout::Std140 => Self::STD140_ALIGNMENT,
+            Layout::Std430 => Self::STD430_ALIGNMENT,
+            Layout::Metal => Self::METAL_ALIGNMENT,
+        }
+    }
+
+    /// The alignments of a `[Self; N]`, which only differ from those of an array of `Self` for
+    /// types that pack their arrays differently, like `bool`.
+    const STD140_ARRAY_ALIGNMENT: usize =
+        Layout::Std140.array_element_alignment(Self::STD140_ALIGNMENT);
+    const STD430_ARRAY_ALIGNMENT: usize =
+        Layout::Std430.array_element_alignment(Self::STD430_ALIGNMENT);
+    const METAL_ARRAY_ALIGNMENT: usize =
+        Layout::Metal.array_element_alignment(Self::METAL_ALIGNMENT);
+}
+
+macro_rules! primitive_impl_std140_std430_matrix {
+    ($datatype:ty, columns = $columns:literal, column = $column:ty) => {
+        primitive_impl_std140_std430_matrix!(@std $datatype, columns = $columns, column = $column);
+
+        impl AsMetal for $datatype {
+            fn as_metal(&self) -> MetalBytes {
+                let mut buf = MetalBytes::new();
+
+                // the columns are laid out like an array, so they follow the layout's array stride
+                let columns: [_; $columns] = std::array::from_fn(|i| self.col(i));
+                buf.write_array(&columns);
+
+                buf.gpu_bytes.matrix = Some(matrix_shape::<$column>($columns, buf.len()));
+
+                buf
+            }
+        }
+
+        impl ShaderSize for $datatype {
+            fn shader_size(layout: Layout) -> usize {
+                let value = <$datatype>::default();
+
+                match layout {
+                    Layout::Std140 => value.as_std140().as_slice().len(),
+                    Layout::Std430 => value.as_std430().as_slice().len(),
+                    Layout::Metal => value.as_metal().as_slice().len(),
+                }
+            }
+        }
+    };
+    // like the vectors they are made of, matrices of 64-bit floats have no Metal equivalent
+    ($datatype:ty, columns = $columns:literal, column = $column:ty, no_metal) => {
+        primitive_impl_std140_std430_matrix!(@std $datatype, columns = $columns, column = $column);
+
+        impl ShaderSize for $datatype {
+            fn shader_size(layout: Layout) -> usize {
+                let value = <$datatype>::default();
+
+                match layout {
+                    Layout::Std140 => value.as_std140().as_slice().len(),
+                    Layout::Std430 => value.as_std430().as_slice().len(),
+                    Layout::Metal => panic!("{} has no Metal equivalent", stringify!($datatype)),
+                }
+            }
+        }
+    };
+    (@std $datatype:ty, columns = $columns:literal, column = $column:ty) => {
+        impl AsStd140 for $datatype {
+            fn as_std140(&self) -> Std140Bytes {
+                let mut buf = Std140Bytes::new();
+
+                // the columns are laid out like an array, so they follow the layout's array stride
+                let columns: [_; $columns] = std::array::from_fn(|i| self.col(i));
+                buf.write_array(&columns);
+
+                buf.gpu_bytes.matrix = Some(matrix_shape::<$column>($columns, buf.len()));
+
+                buf
+            }
+        }
+
+        impl AsStd430 for $datatype {
+            fn as_std430(&self) -> Std430Bytes {
+                let mut buf = Std430Bytes::new();
+
+                // the columns are laid out like an array, so they follow the layout's array stride
+                let columns: [_; $columns] = std::array::from_fn(|i| self.col(i));
+                buf.write_array(&columns);
+
+                buf.gpu_bytes.matrix = Some(matrix_shape::<$column>($columns, buf.len()));
+
+                buf
+            }
+        }
+
+        impl FromStd430 for $datatype {
+            fn from_std430(bytes: &[u8]) -> Result<Self, GpuBytesError> {
+                let stride = std::mem::size_of::<$column>().next_multiple_of(
+                    Layout::Std430.array_element_alignment(<$column>::STD430_ALIGNMENT),
+                );
+
+                let mut scalars = Vec::new();
+
+                for i in 0..$columns {
+                    let column =
+                        <$column>::from_std430(bytes.get(i * stride..).unwrap_or_default())?;
+                    scalars.extend(column.to_array());
+                }
+
+                Ok(<$datatype>::from_cols_slice(&scalars))
+            }
+        }
+
+        // the columns are aligned like array elements
+        impl ShaderAlignment for $datatype {
+            const STD140_ALIGNMENT: usize =
+                Layout::Std140.array_element_alignment(<$column>::STD140_ALIGNMENT);
+            const STD430_ALIGNMENT: usize =
+                Layout::Std430.array_element_alignment(<$column>::STD430_ALIGNMENT);
+            const METAL_ALIGNMENT: usize =
+                Layout::Metal.array_element_alignment(<$column>::METAL_ALIGNMENT);
+        }
+    };
+}
+
+// the column count, column stride and component size of a square matrix with `columns` columns
+// of type `C`, taking up `len` bytes
+fn matrix_shape<C>(columns: usize, len: usize) -> (usize, usize, usize) {
+    (columns, len / columns, std::mem::size_of::<C>() / columns)
+}
+
+/// Writes `C` column vectors as a std140 matrix, aligning each column like an array element.
+pub fn write_mat<const C: usize, V: AsStd140>(buf: &mut Std140Bytes, columns: [V; C]) {
+    buf.write_array(&columns);
+}
+
+primitive_impl_std140_std430!(f32, align = 4);
+primitive_impl_std140_std430!(glam::Vec2, align = 8);
 primitive_impl_std140_std430!(glam::Vec3, align = 16);
 primitive_impl_std140_std430!(glam::Vec4, align = 16);
+// a quaternion is written as a vec4 of `x, y, z, w`
+primitive_impl_std140_std430!(glam::Quat, align = 16);
+
+primitive_impl_std140_std430!(i32, align = 4);
+primitive_impl_std140_std430!(glam::IVec2, align = 8);
+primitive_impl_std140_std430!(glam::IVec3, align = 16);
+primitive_impl_std140_std430!(glam::IVec4, align = 16);
+
+primitive_impl_std140_std430!(u32, align = 4);
+primitive_impl_std140_std430!(glam::UVec2, align = 8);
+primitive_impl_std140_std430!(glam::UVec3, align = 16);
+primitive_impl_std140_std430!(glam::UVec4, align = 16);
+
+primitive_impl_std140_std430!(u64, align = 8, component = 8);
+primitive_impl_std140_std430!(i64, align = 8, component = 8);
+
+primitive_impl_std140_std430!(f64, align = 8, component = 8, no_metal);
+primitive_impl_std140_std430!(glam::DVec2, align = 16, component = 8, no_metal);
+// a `DVec3` is 24 bytes, the same as a shader `vec3<f64>`, so it only needs the larger alignment
+primitive_impl_std140_std430!(glam::DVec3, align = 32, component = 8, no_metal);
+primitive_impl_std140_std430!(glam::DVec4, align = 32, component = 8, no_metal);
+
+primitive_impl_std140_std430_matrix!(glam::Mat2, columns = 2, column = glam::Vec2);
+primitive_impl_std140_std430_matrix!(glam::Mat3, columns = 3, column = glam::Vec3);
+primitive_impl_std140_std430_matrix!(glam::Mat4, columns = 4, column = glam::Vec4);
+primitive_impl_std140_std430_matrix!(glam::DMat2, columns = 2, column = glam::DVec2, no_metal);
+
+/// Reads a value back from the std430 bytes it was written as, e.g. from a buffer the GPU wrote.
+pub trait FromStd430: Sized {
+    fn from_std430(bytes: &[u8]) -> Result<Self, GpuBytesError>;
+}
+
+/// Reads consecutive values from std430 bytes, skipping the padding a [`Std430Bytes`] would have
+/// inserted in front of each.
+#[derive(Debug, Clone)]
+pub struct Std430Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Std430Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    pub fn read<T: FromStd430 + AsStd430 + ShaderAlignment>(&mut self) -> Result<T, GpuBytesError> {
+        let offset = self.offset.next_multiple_of(T::STD430_ALIGNMENT);
+        let value = T::from_std430(self.bytes.get(offset..).unwrap_or_default())?;
+
+        // the value is written again to find its size, so that it always agrees with `write`
+        self.offset = offset + value.as_std430().len();
+
+        Ok(value)
+    }
+
+    /// The offset the next value will be read from, before any padding.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+/// The name of a type in WGSL, e.g. `vec3<f32>` for a `Vec3`, used to describe fields in a
+/// [`LayoutInfo`](struct@LayoutInfo).
+pub trait WgslType {
+    fn wgsl_type() -> String;
+}
+
+macro_rules! wgsl_type {
+    ($($datatype:ty => $name:literal),+ $(,)?) => {
+        $(impl WgslType for $datatype {
+            fn wgsl_type() -> String {
+                $name.to_string()
+            }
+        })+
+    };
+}
+
+wgsl_type!(
+    f32 => "f32", glam::Vec2 => "vec2<f32>", glam::Vec3 => "vec3<f32>", glam::Vec4 => "vec4<f32>",
+    glam::Quat => "vec4<f32>",
+    i32 => "i32", glam::IVec2 => "vec2<i32>", glam::IVec3 => "vec3<i32>", glam::IVec4 => "vec4<i32>",
+    u32 => "u32", glam::UVec2 => "vec2<u32>", glam::UVec3 => "vec3<u32>", glam::UVec4 => "vec4<u32>",
+    f64 => "f64", glam::DVec2 => "vec2<f64>", glam::DVec3 => "vec3<f64>", glam::DVec4 => "vec4<f64>",
+    glam::Mat2 => "mat2x2<f32>", glam::Mat3 => "mat3x3<f32>", glam::Mat4 => "mat4x4<f32>",
+    glam::DMat2 => "mat2x2<f64>",
+);
+
+impl<T: ShaderAlignment, const N: usize> ShaderAlignment for [T; N] {
+    const STD140_ALIGNMENT: usize = T::STD140_ARRAY_ALIGNMENT;
+    const STD430_ALIGNMENT: usize = T::STD430_ARRAY_ALIGNMENT;
+    const METAL_ALIGNMENT: usize = T::METAL_ARRAY_ALIGNMENT;
+}
+
+// every element is padded to the array stride, including the last one
+impl<T: ShaderSize + ShaderAlignment, const N: usize> ShaderSize for [T; N] {
+    fn shader_size(layout: Layout) -> usize {
+        let stride = T::shader_size(layout).next_multiple_of(Self::shader_alignment(layout).max(1));
+
+        N * stride
+    }
+}
+
+impl<T: WgslType, const N: usize> WgslType for [T; N] {
+    fn wgsl_type() -> String {
+        format!("array<{}, {N}>", T::wgsl_type())
+    }
+}
+
+/// A description of how a struct is laid out, generated by `#[derive(LayoutInfo)]` for tools
+/// that inspect buffers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutInfo {
+    pub name: &'static str,
+    /// The size of the struct, including its trailing padding.
+    pub size: usize,
+    pub alignment: usize,
+    pub fields: Vec<FieldInfo>,
+}
+
+impl LayoutInfo {
+    /// Generates the WGSL declaration of the struct.
+    pub fn to_wgsl_struct(&self) -> String {
+        self.wgsl_struct(false)
+    }
+
+    /// Like [`Self::to_wgsl_struct`], but with an explicit `_padN: u32` member for every 4 bytes
+    /// of padding in front of a field, so the declaration spells out the whole layout. Trailing
+    /// padding is left implicit, since WGSL rounds the struct size up in the same way.
+    pub fn to_wgsl_struct_with_padding(&self) -> String {
+        self.wgsl_struct(true)
+    }
+
+    fn wgsl_struct(&self, explicit_padding: bool) -> String {
+        let mut wgsl = format!("struct {} {{\n", self.name);
+        let mut end = 0;
+        let mut pad = 0;
+
+        for field in self.fields.iter() {
+            if explicit_padding {
+                for _ in 0..(field.offset - end) / 4 {
+                    wgsl.push_str(&format!("    _pad{pad}: u32,\n"));
+                    pad += 1;
+                }
+            }
+
+            wgsl.push_str(&format!("    {}: {},\n", field.name, field.wgsl_type));
+            end = field.offset + field.size;
+        }
+
+        wgsl.push('}');
+        wgsl
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldInfo {
+    pub name: &'static str,
+    pub wgsl_type: String,
+    pub offset: usize,
+    pub size: usize,
+}
+
+// the vec is sized for its capacity rather than its length, so an empty vec must have reserved
+// space; prefer `try_as_std140` when the capacity might be zero
+impl<T: AsStd140 + Default> AsStd140 for Vec<T> {
+    fn as_std140(&self) -> Std140Bytes {
+        self.try_as_std140().unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    fn try_as_std140(&self) -> Result<Std140Bytes, GpuBytesError> {
+        let mut buf = Std140Bytes::new();
+
+        if self.capacity() == 0 {
+            return Err(GpuBytesError::ZeroCapacityVec);
+        }
+
+        let mut std140 = T::default().as_std140();
+
+        // in std140, array elements are aligned to a multiple of 16
+        std140.align_to(Layout::Std140.array_element_alignment(std140.gpu_bytes.alignment));
+
+        let bytes_per_element = std140.as_slice().len();
+        let mut alignment = std140.gpu_bytes.alignment;
+
+        // the gpu representation will contain as many bytes as possible to hold the vec's capacity
+        // and fill the appropriate number of bytes with the vec's elements
+        let total_bytes = bytes_per_element * self.capacity();
+
+        for elem in self.iter() {
+            let mut std140 = elem.as_std140();
+
+            // in std140, array elements are aligned to a multiple of 16
+            std140.align_to(Layout::Std140.array_element_alignment(std140.gpu_bytes.alignment));
+            alignment = alignment.max(std140.gpu_bytes.alignment);
+
+            buf.gpu_bytes.append(&std140.gpu_bytes);
+        }
+
+        // now pad with 0's for the remaining capacity
+        let padding = total_bytes - buf.gpu_bytes.bytes.len();
+
+        buf.gpu_bytes
+            .bytes
+            .extend(std::iter::repeat_n(0u8, padding));
+        buf.gpu_bytes.padding += padding;
+
+        buf.gpu_bytes.alignment = alignment;
+
+        Ok(buf)
+    }
+}
+
+// the vec is sized for its capacity rather than its length, so an empty vec must have reserved
+// space; prefer `try_as_std430` when the capacity might be zero
+impl<T: AsStd430 + Default> AsStd430 for Vec<T> {
+    fn as_std430(&self) -> Std430Bytes {
+        self.try_as_std430().unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    fn try_as_std430(&self) -> Result<Std430Bytes, GpuBytesError> {
+        let mut buf = Std430Bytes::new();
+
+        if self.capacity() == 0 {
+            return Err(GpuBytesError::ZeroCapacityVec);
+        }
+
+        let mut std430 = T::default().as_std430();
+        std430.align();
+
+        let bytes_per_element = std430.as_slice().len();
+        let mut alignment = std430.gpu_bytes.alignment;
+
+        // the gpu representation will contain as many bytes as possible to hold the vec's capacity
+        // and fill the appropriate number of bytes with the vec's elements
+        let total_bytes = bytes_per_element * self.capacity();
+
+        for elem in self.iter() {
+            let mut std430 = elem.as_std430();
+            std430.align();
+            alignment = alignment.max(std430.gpu_bytes.alignment);
+
+            buf.gpu_bytes.append(&std430.gpu_bytes);
+        }
+
+        // now pad with 0's for the remaining capacity
+        let padding = total_bytes - buf.gpu_bytes.bytes.len();
+
+        buf.gpu_bytes
+            .bytes
+            .extend(std::iter::repeat_n(0u8, padding));
+        buf.gpu_bytes.padding += padding;
+
+        // the alignment of the array is the largest alignment of its elements in std430
+        buf.gpu_bytes.alignment = alignment;
+
+        Ok(buf)
+    }
+}
+
+// the vec is sized for its capacity rather than its length, so an empty vec must have reserved
+// space; prefer `try_as_metal` when the capacity might be zero
+impl<T: AsMetal + Default> AsMetal for Vec<T> {
+    fn as_metal(&self) -> MetalBytes {
+        self.try_as_metal().unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    fn try_as_metal(&self) -> Result<MetalBytes, GpuBytesError> {
+        let mut buf = MetalBytes::new();
+
+        if self.capacity() == 0 {
+            return Err(GpuBytesError::ZeroCapacityVec);
+        }
+
+        let mut metal = T::default().as_metal();
+        metal.align();
+
+        let bytes_per_element = metal.as_slice().len();
+        let mut alignment = metal.gpu_bytes.alignment;
+
+        // the gpu representation will contain as many bytes as possible to hold the vec's capacity
+        // and fill the appropriate number of bytes with the vec's elements
+        let total_bytes = bytes_per_element * self.capacity();
+
+        for elem in self.iter() {
+            let mut metal = elem.as_metal();
+            metal.align();
+            alignment = alignment.max(metal.gpu_bytes.alignment);
+
+            buf.gpu_bytes.append(&metal.gpu_bytes);
+        }
+
+        // now pad with 0's for the remaining capacity
+        let padding = total_bytes - buf.gpu_bytes.bytes.len();
+
+        buf.gpu_bytes
+            .bytes
+            .extend(std::iter::repeat_n(0u8, padding));
+        buf.gpu_bytes.padding += padding;
+
+        // the alignment of the array is the largest alignment of its elements in metal
+        buf.gpu_bytes.alignment = alignment;
+
+        Ok(buf)
+    }
+}
+
+// written as two consecutive values, `start` followed by the inclusive `end`
+impl<T: AsStd140> AsStd140 for std::ops::RangeInclusive<T> {
+    fn as_std140(&self) -> Std140Bytes {
+        let mut buf = Std140Bytes::new();
+
+        buf.write(self.start());
+        buf.write(self.end());
+
+        buf
+    }
+}
+
+impl<T: AsStd430> AsStd430 for std::ops::RangeInclusive<T> {
+    fn as_std430(&self) -> Std430Bytes {
+        let mut buf = Std430Bytes::new();
+
+        buf.write(self.start());
+        buf.write(self.end());
+
+        buf
+    }
+}
+
+impl<T: AsMetal> AsMetal for std::ops::RangeInclusive<T> {
+    fn as_metal(&self) -> MetalBytes {
+        let mut buf = MetalBytes::new();
+
+        buf.write(self.start());
+        buf.write(self.end());
+
+        buf
+    }
+}
+
+/// A complex number, written as a `vec2<f32>` of its real and imaginary parts.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Complex {
+    pub re: f32,
+    pub im: f32,
+}
+
+impl Complex {
+    pub fn new(re: f32, im: f32) -> Self {
+        Self { re, im }
+    }
+}
+
+impl AsStd140 for Complex {
+    fn as_std140(&self) -> Std140Bytes {
+        glam::Vec2::new(self.re, self.im).as_std140()
+    }
+}
+
+impl AsStd430 for Complex {
+    fn as_std430(&self) -> Std430Bytes {
+        glam::Vec2::new(self.re, self.im).as_std430()
+    }
+}
+
+impl AsMetal for Complex {
+    fn as_metal(&self) -> MetalBytes {
+        glam::Vec2::new(self.re, self.im).as_metal()
+    }
+}
+
+/// A 4-component vector stored as a plain array, written as the `vec4` of the same scalar type,
+/// e.g. for 128-bit masks kept as `[u32; 4]` that the shader reads as a `vec4<u32>`. A bare
+/// `[u32; 4]` is written as an array instead, which in std140 pads each element to 16 bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Vector4<T>(pub [T; 4]);
+
+impl<T> From<[T; 4]> for Vector4<T> {
+    fn from(array: [T; 4]) -> Self {
+        Self(array)
+    }
+}
+
+macro_rules! vector4_impl {
+    ($($scalar:ty => $vector:ty),+ $(,)?) => {
+        $(
+            impl AsStd140 for Vector4<$scalar> {
+                fn as_std140(&self) -> Std140Bytes {
+                    <$vector>::from_array(self.0).as_std140()
+                }
+            }
+
+            impl AsStd430 for Vector4<$scalar> {
+                fn as_std430(&self) -> Std430Bytes {
+                    <$vector>::from_array(self.0).as_std430()
+                }
+            }
+
+            impl AsMetal for Vector4<$scalar> {
+                fn as_metal(&self) -> MetalBytes {
+                    <$vector>::from_array(self.0).as_metal()
+                }
+            }
+
+            impl FromStd430 for Vector4<$scalar> {
+                fn from_std430(bytes: &[u8]) -> Result<Self, GpuBytesError> {
+                    <$vector>::from_std430(bytes).map(|v| Self(v.to_array()))
+                }
+            }
+
+            impl ShaderAlignment for Vector4<$scalar> {
+                const STD140_ALIGNMENT: usize = <$vector>::STD140_ALIGNMENT;
+                const STD430_ALIGNMENT: usize = <$vector>::STD430_ALIGNMENT;
+                const METAL_ALIGNMENT: usize = <$vector>::METAL_ALIGNMENT;
+            }
+
+            impl ShaderSize for Vector4<$scalar> {
+                fn shader_size(layout: Layout) -> usize {
+                    <$vector>::shader_size(layout)
+                }
+            }
+
+            impl WgslType for Vector4<$scalar> {
+                fn wgsl_type() -> String {
+                    <$vector>::wgsl_type()
+                }
+            }
+        )+
+    };
+}
+
+vector4_impl!(f32 => glam::Vec4, i32 => glam::IVec4, u32 => glam::UVec4);
+
+/// A `vec4<f32>` packed into a `u32` like WGSL's `pack4x8unorm`, with component `i` in bits
+/// `8 * i..8 * i + 8`. It is unpacked in the shader with `unpack4x8unorm`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PackedUnorm4x8(pub u32);
+
+impl PackedUnorm4x8 {
+    pub fn from_vec4(v: glam::Vec4) -> Self {
+        let bytes = v
+            .to_array()
+            .map(|c| (c.clamp(0.0, 1.0) * 255.0 + 0.5).floor() as u8);
+
+        Self(u32::from_le_bytes(bytes))
+    }
+}
+
+impl AsStd140 for PackedUnorm4x8 {
+    fn as_std140(&self) -> Std140Bytes {
+        self.0.as_std140()
+    }
+}
+
+impl AsStd430 for PackedUnorm4x8 {
+    fn as_std430(&self) -> Std430Bytes {
+        self.0.as_std430()
+    }
+}
+
+impl AsMetal for PackedUnorm4x8 {
+    fn as_metal(&self) -> MetalBytes {
+        self.0.as_metal()
+    }
+}
+
+/// A value written as a 16-bit signed fixed-point number with `FRAC` fractional bits, e.g. `1.5`
+/// with 8 fractional bits is written as `0x0180`. `FRAC` can be at most 15, and values that
+/// don't fit saturate to `i16::MIN` or `i16::MAX`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Fixed16<const FRAC: u32>(pub f32);
+
+impl<const FRAC: u32> Fixed16<FRAC> {
+    pub fn to_bits(self) -> i16 {
+        const { assert!(FRAC <= 15, "a Fixed16 has at most 15 fractional bits") };
+
+        (self.0 * (1u32 << FRAC) as f32).round() as i16
+    }
+}
+
+impl<const FRAC: u32> AsStd140 for Fixed16<FRAC> {
+    fn as_std140(&self) -> Std140Bytes {
+        let mut buf = Std140Bytes::new();
+
+        write_scalar_le(&mut buf.gpu_bytes, &self.to_bits(), 2, 2);
+
+        buf
+    }
+}
+
+impl<const FRAC: u32> AsStd430 for Fixed16<FRAC> {
+    fn as_std430(&self) -> Std430Bytes {
+        let mut buf = Std430Bytes::new();
+
+        write_scalar_le(&mut buf.gpu_bytes, &self.to_bits(), 2, 2);
+
+        buf
+    }
+}
+
+impl<const FRAC: u32> AsMetal for Fixed16<FRAC> {
+    fn as_metal(&self) -> MetalBytes {
+        let mut buf = MetalBytes::new();
+
+        write_scalar_le(&mut buf.gpu_bytes, &self.to_bits(), 2, 2);
+
+        buf
+    }
+}
+
+// glsl and wgsl read a `bool` in a buffer as a 4-byte value that is either 0 or 1, while an MSL
+// `bool` is a single byte. A `[bool; N]` is bit packed into u32 words instead in every layout,
+// with flag `i` in bit `i % 32` of word `i / 32`, read in std430 and Metal as an `array<u32, W>`.
+// std140 pads the elements of such an array to 16 bytes, so it only allows a single word
+impl ShaderAlignment for bool {
+    const STD140_ALIGNMENT: usize = 4;
+    const STD430_ALIGNMENT: usize = 4;
+    const METAL_ALIGNMENT: usize = 1;
+
+    const STD140_ARRAY_ALIGNMENT: usize = 4;
+    const STD430_ARRAY_ALIGNMENT: usize = 4;
+    const METAL_ARRAY_ALIGNMENT: usize = 4;
+}
+
+fn write_bitfield(buf: &mut GpuBytes, flags: &[bool]) {
+    for chunk in flags.chunks(32) {
+        let word = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |word, (bit, &flag)| word | ((flag as u32) << bit));
+
+        write_scalar_le(buf, &word, 4, 4);
+    }
+
+    buf.alignment = 4;
+}
+
+impl AsStd140 for bool {
+    fn as_std140(&self) -> Std140Bytes {
+        (*self as u32).as_std140()
+    }
+
+    fn array_as_std140<const N: usize>(array: &[Self; N]) -> Std140Bytes {
+        // a std140 `array<u32, W>` has a stride of 16 bytes, so only a single word can be read
+        // as the tightly packed u32 that is written
+        const {
+            assert!(
+                N <= 32,
+                "a std140 [bool; N] is packed into one u32, so N can be at most 32"
+            )
+        };
+
+        let mut buf = Std140Bytes::new();
+
+        write_bitfield(&mut buf.gpu_bytes, array);
+
+        buf
+    }
+}
+
+impl AsStd430 for bool {
+    fn as_std430(&self) -> Std430Bytes {
+        (*self as u32).as_std430()
+    }
+
+    fn array_as_std430<const N: usize>(array: &[Self; N]) -> Std430Bytes {
+        let mut buf = Std430Bytes::new();
+
+        write_bitfield(&mut buf.gpu_bytes, array);
+
+        buf
+    }
+}
+
+impl AsMetal for bool {
+    fn as_metal(&self) -> MetalBytes {
+        let mut buf = MetalBytes::new();
+
+        write_scalar_le(&mut buf.gpu_bytes, &(*self as u8), 1, 1);
+
+        buf
+    }
+
+    fn array_as_metal<const N: usize>(array: &[Self; N]) -> MetalBytes {
+        let mut buf = MetalBytes::new();
+
+        write_bitfield(&mut buf.gpu_bytes, array);
+
+        buf
+    }
+}
+
+// a duration is written as its fractional seconds, which is what time uniforms usually want
+impl AsStd140 for std::time::Duration {
+    fn as_std140(&self) -> Std140Bytes {
+        self.as_secs_f32().as_std140()
+    }
+}
+
+impl AsStd430 for std::time::Duration {
+    fn as_std430(&self) -> Std430Bytes {
+        self.as_secs_f32().as_std430()
+    }
+}
+
+impl AsMetal for std::time::Duration {
+    fn as_metal(&self) -> MetalBytes {
+        self.as_secs_f32().as_metal()
+    }
+}
+
+// an ipv4 address is written as the u32 whose most significant byte is its first octet, i.e.
+// `u32::from(addr)`, so `127.0.0.1` is `0x7f000001`
+impl AsStd140 for std::net::Ipv4Addr {
+    fn as_std140(&self) -> Std140Bytes {
+        u32::from(*self).as_std140()
+    }
+}
+
+impl AsStd430 for std::net::Ipv4Addr {
+    fn as_std430(&self) -> Std430Bytes {
+        u32::from(*self).as_std430()
+    }
+}
+
+impl AsMetal for std::net::Ipv4Addr {
+    fn as_metal(&self) -> MetalBytes {
+        u32::from(*self).as_metal()
+    }
+}
+impl<T: AsStd140, const N: usize> AsStd140 for [T; N] {
+    fn as_std140(&self) -> Std140Bytes {
+        T::array_as_std140(self)
+    }
+}
+
+impl<T: AsStd430, const N: usize> AsStd430 for [T; N] {
+    fn as_std430(&self) -> Std430Bytes {
+        T::array_as_std430(self)
+    }
+}
+
+impl<T: AsMetal, const N: usize> AsMetal for [T; N] {
+    fn as_metal(&self) -> MetalBytes {
+        T::array_as_metal(self)
+    }
+}
+
+impl<T: AsStd140> AsStd140 for &[T] {
+    fn as_std140(&self) -> Std140Bytes {
+        let mut buf = Std140Bytes::new();
+
+        buf.write_array(self);
+
+        // an empty array doesn't constrain the alignment
+        buf.gpu_bytes.alignment = buf.gpu_bytes.alignment.max(1);
+
+        buf
+    }
+}
+
+impl<T: AsStd430> AsStd430 for &[T] {
+    fn as_std430(&self) -> Std430Bytes {
+        let mut buf = Std430Bytes::new();
+
+        buf.write_array(self);
+
+        // an empty array doesn't constrain the alignment
+        buf.gpu_bytes.alignment = buf.gpu_bytes.alignment.max(1);
+
+        buf
+    }
+}
+
+impl<T: AsMetal> AsMetal for &[T] {
+    fn as_metal(&self) -> MetalBytes {
+        let mut buf = MetalBytes::new();
+
+        buf.write_array(self);
+
+        // an empty array doesn't constrain the alignment
+        buf.gpu_bytes.alignment = buf.gpu_bytes.alignment.max(1);
+
+        buf
+    }
+}
+
+// tuples are written like structs, with each element in order
+macro_rules! tuple_impl_std140_std430 {
+    ($($name:ident),+) => {
+        impl<$($name: AsStd140),+> AsStd140 for ($($name,)+) {
+            fn as_std140(&self) -> Std140Bytes {
+                let mut buf = Std140Bytes::new();
+
+                #[allow(non_snake_case)]
+                let ($($name,)+) = self;
+                $(buf.write($name);)+
+                buf.align();
+
+                buf
+            }
+        }
+
+        impl<$($name: AsStd430),+> AsStd430 for ($($name,)+) {
+            fn as_std430(&self) -> Std430Bytes {
+                let mut buf = Std430Bytes::new();
+
+                #[allow(non_snake_case)]
+                let ($($name,)+) = self;
+                $(buf.write($name);)+
+                buf.align();
+
+                buf
+            }
+        }
+
+        impl<$($name: AsMetal),+> AsMetal for ($($name,)+) {
+            fn as_metal(&self) -> MetalBytes {
+                let mut buf = MetalBytes::new();
+
+                #[allow(non_snake_case)]
+                let ($($name,)+) = self;
+                $(buf.write($name);)+
+                buf.align();
+
+                buf
+            }
+        }
+    };
+}
+
+tuple_impl_std140_std430!(A);
+tuple_impl_std140_std430!(A, B);
+tuple_impl_std140_std430!(A, B, C);
+tuple_impl_std140_std430!(A, B, C, D);
+tuple_impl_std140_std430!(A, B, C, D, E);
+tuple_impl_std140_std430!(A, B, C, D, E, F);
+tuple_impl_std140_std430!(A, B, C, D, E, F, G);
+tuple_impl_std140_std430!(A, B, C, D, E, F, G, H);
+
+/// Serializes a runtime-sized std430 array, e.g. `array<T>` at the end of a storage buffer.
+pub trait AsStd430RuntimeArray {
+    /// Unlike the `Vec<T>` impl of [`AsStd430`], the size ignores any spare capacity, so that
+    /// `arrayLength()` in the shader returns the number of elements.
+    fn as_std430_array_length_exact(&self) -> Std430Bytes;
+}
+
+impl<T: AsStd430> AsStd430RuntimeArray for [T] {
+    fn as_std430_array_length_exact(&self) -> Std430Bytes {
+        self.as_std430()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::UVec3;
+
+    use super::*;
+
+    #[test]
+    fn std140_vec3_and_scalar() {
+        let mut buf = Std140Bytes::new();
+
+        buf.write(&UVec3::splat(u32::MAX));
+        buf.write(&u32::MAX);
+        buf.align();
+
+        #[rustfmt::skip]
+        assert_eq!(
+            buf.as_slice(),
+            &[
+                // x
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                // y
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                // z
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                // scalar
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+            ]
+        );
+    }
+
+    #[test]
+    fn std140_vec3_and_vec3() {
+        let mut buf = Std140Bytes::new();
+
+        buf.write(&UVec3::splat(u32::MAX));
+        buf.write(&UVec3::splat(u32::MAX));
+        buf.align();
+
+        #[rustfmt::skip]
+        assert_eq!(
+            buf.as_slice(),
+            &[
+                // x
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                // y
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                // z
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                // padding
+                0, 0, 0, 0, 
+                // x
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                // y
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                // z
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                // padding
+                0, 0, 0, 0, 
+            ]
+        );
+    }
+
+    #[test]
+    fn std140_scalar_and_vec3() {
+        let mut buf = Std140Bytes::new();
+
+        buf.write(&u32::MAX);
+        buf.write(&UVec3::splat(u32::MAX));
+        buf.align();
+
+        #[rustfmt::skip]
+        assert_eq!(
+            buf.as_slice(),
+            &[
+                // scalar
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                // padding
+                0, 0, 0, 0, 
+                // padding
+                0, 0, 0, 0, 
+                // padding
+                0, 0, 0, 0, 
+                // x
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                // y
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                // z
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                // padding
+                0, 0, 0, 0, 
+            ]
+        );
+    }
+
+    #[test]
+    fn std140_scalar_array() {
+        let mut buf = Std140Bytes::new();
+
+        buf.write_array(&[u32::MAX, u32::MAX]);
+        buf.align();
+
+        #[rustfmt::skip]
+        assert_eq!(
+            buf.as_slice(),
+            &[
+                // scalar
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                // padding
+                0, 0, 0, 0, 
+                // padding
+                0, 0, 0, 0, 
+                // padding
+                0, 0, 0, 0, 
+                // scalar
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                // padding
+                0, 0, 0, 0, 
+                // padding
+                0, 0, 0, 0, 
+                // padding
+                0, 0, 0, 0, 
+            ]
+        );
+    }
+
+    #[test]
+    fn std430_scalar_array() {
+        let mut buf = Std430Bytes::new();
+
+        buf.write_array(&[u32::MAX, u32::MAX]);
+        buf.align();
+
+        #[rustfmt::skip]
+        assert_eq!(
+            buf.as_slice(),
+            &[
+                // scalar
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                // scalar
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+            ]
+        );
+    }
+
+    #[test]
+    fn std140_from_bytes_checked() {
+        let buf = Std140Bytes::from_bytes_checked(vec![0; 32], 16).unwrap();
+        assert_eq!(buf.as_slice(), &[0; 32]);
+
+        assert_eq!(
+            Std140Bytes::from_bytes_checked(vec![0; 20], 16).unwrap_err(),
+            GpuBytesError::MisalignedLength {
+                len: 20,
+                alignment: 16
+            }
+        );
+
+        // 24 bytes are a multiple of 12, but no shader type has an alignment of 12
+        assert_eq!(
+            Std140Bytes::from_bytes_checked(vec![0; 24], 12).unwrap_err(),
+            GpuBytesError::InvalidAlignment(12)
+        );
+    }
+
+    #[test]
+    fn std140_spatial_pairs() {
+        use glam::IVec3;
+
+        let mut buf = Std140Bytes::new();
+
+        buf.write_spatial_pairs(&[
+            (IVec3::new(1, 1, 1), 0),
+            (IVec3::new(-1, 0, 0), 1),
+            (IVec3::new(0, 0, 1), 2),
+            (IVec3::new(0, 0, 0), 3),
+        ]);
+
+        let mut expected = Std140Bytes::new();
+
+        for (cell, index) in [
+            (IVec3::new(-1, 0, 0), 1u32),
+            (IVec3::new(0, 0, 0), 3),
+            (IVec3::new(0, 0, 1), 2),
+            (IVec3::new(1, 1, 1), 0),
+        ] {
+            expected.write(&cell);
+            expected.write(&index);
+        }
+
+        assert_eq!(buf.as_slice().len(), 4 * 16);
+        assert_eq!(buf.as_slice(), expected.as_slice());
+
+        let mut buf = MetalBytes::new();
+        buf.write_spatial_pairs(&[(IVec3::ONE, 0), (IVec3::ZERO, 1)]);
+
+        // each `vec3<i32>` fills a slot of its own, so the pairs are 32 bytes apart
+        assert_eq!(buf.as_slice()[16..20], 1u32.to_le_bytes());
+        assert_eq!(buf.as_slice()[48..], 0u32.to_le_bytes());
+    }
+
+    #[test]
+    fn size_counter_matches_builder() {
+        use glam::{Mat3, Vec2, Vec3};
+
+        let mut buf = Std140Bytes::new();
+        let mut counter = Std140SizeCounter::new();
+
+        buf.write(&1.0f32)
+            .write(&Vec3::ONE)
+            .write_array(&[Vec2::ONE, Vec2::ZERO, Vec2::X])
+            .write(&Mat3::IDENTITY)
+            .write(&2u32)
+            .align();
+        counter
+            .write(&1.0f32)
+            .write(&Vec3::ONE)
+            .write_array(&[Vec2::ONE, Vec2::ZERO, Vec2::X])
+            .write(&Mat3::IDENTITY)
+            .write(&2u32)
+            .align();
+
+        assert_eq!(counter.len(), buf.as_slice().len());
+
+        let mut buf = Std430Bytes::new();
+        let mut counter = Std430SizeCounter::new();
+
+        buf.write(&1.0f32)
+            .write_array(&[Vec2::ONE, Vec2::ZERO, Vec2::X])
+            .write(&Vec3::ONE)
+            .align();
+        counter
+            .write(&1.0f32)
+            .write_array(&[Vec2::ONE, Vec2::ZERO, Vec2::X])
+            .write(&Vec3::ONE)
+            .align();
+
+        assert_eq!(counter.len(), buf.as_slice().len());
+    }
+
+    #[test]
+    fn std140_tangent() {
+        use glam::{Vec3, Vec4};
+
+        let mut buf = Std140Bytes::new();
+        buf.write(&1.0f32);
+        buf.write_tangent(Vec3::new(1.0, 2.0, 3.0), -1.0);
+
+        let mut expected = Std140Bytes::new();
+        expected.write(&1.0f32);
+        expected.write(&Vec4::new(1.0, 2.0, 3.0, -1.0));
+
+        assert_eq!(buf.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn gpu_buffer_matches_dedicated_builders() {
+        use glam::Vec3;
+
+        let mut buf = GpuBuffer::new(Layout::Std140);
+        buf.write(&1u32)
+            .write_array(&[2u32, 3])
+            .write(&Vec3::ONE)
+            .align();
+
+        let mut expected = Std140Bytes::new();
+        expected
+            .write(&1u32)
+            .write_array(&[2u32, 3])
+            .write(&Vec3::ONE)
+            .align();
+
+        assert_eq!(buf.layout(), Layout::Std140);
+        assert_eq!(buf.as_slice(), expected.as_slice());
+
+        let mut buf = GpuBuffer::new(Layout::Std430);
+        buf.write(&1u32)
+            .write_array(&[2u32, 3])
+            .write(&Vec3::ONE)
+            .align();
+
+        let mut expected = Std430Bytes::new();
+        expected
+            .write(&1u32)
+            .write_array(&[2u32, 3])
+            .write(&Vec3::ONE)
+            .align();
+
+        assert_eq!(buf.layout(), Layout::Std430);
+        assert_eq!(buf.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn std430_sorted_map() {
+        let mut map = std::collections::BTreeMap::new();
+        map.insert(3, 30u32);
+        map.insert(1, 10u32);
+        map.insert(2, 20u32);
+
+        let mut buf = Std430Bytes::new();
+        buf.write_sorted_map(&map);
+
+        let mut expected = Std430Bytes::new();
+        expected.write_array(&[10u32, 20, 30]);
+
+        assert_eq!(buf.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn std140_padding_bytes() {
+        let mut buf = Std140Bytes::new();
+
+        buf.write(&1.0f32);
+        buf.write(&glam::Vec3::ONE);
+        buf.align();
+
+        // 12 bytes before the vec3, 4 bytes after it
+        assert_eq!(buf.padding_bytes(), 16);
+        assert_eq!(buf.as_slice().len() - buf.padding_bytes(), 16);
+    }
+
+    #[test]
+    fn std140_rect() {
+        use glam::{Vec2, Vec4};
+
+        let min = Vec2::new(0.25, 0.5);
+        let max = Vec2::new(0.75, 1.0);
+
+        let mut buf = Std140Bytes::new();
+        buf.write_rect(min, max);
+
+        let mut expected = Std140Bytes::new();
+        expected.write(&Vec4::new(min.x, min.y, max.x, max.y));
+
+        assert_eq!(buf.as_slice().len(), 16);
+        assert_eq!(buf.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "not a power of two")]
+    fn write_non_power_of_two_alignment() {
+        let malformed = Std140Bytes::from_bytes(vec![0; 3], 3);
+
+        Std140Bytes::new().write(&malformed);
+    }
+
+    #[test]
+    fn write_zero_alignment() {
+        // empty buffers place no constraint on where they are written
+        let mut buf = Std140Bytes::new();
+        buf.write(&1u32)
+            .write(&Std140Bytes::new())
+            .write(&Std140Bytes::from_bytes(vec![], 0))
+            .write_array(&[Std140Bytes::new()]);
+
+        assert_eq!(buf.as_slice(), 1u32.as_std140().as_slice());
+
+        let mut buf = Std430Bytes::new();
+        buf.write(&1u32).write_array(&[Std430Bytes::new()]);
+
+        assert_eq!(buf.as_slice(), 1u32.as_std430().as_slice());
+
+        let mut buf = Std140Bytes::new();
+        buf.write_array_with_stride(&[Std140Bytes::new()], 16)
+            .unwrap();
+
+        assert_eq!(buf.as_slice(), [0; 16]);
+    }
+
+    #[test]
+    fn std140_vec4_array_from_f32() {
+        use glam::Vec4;
+
+        let data = [[1.0, 2.0, 3.0, 4.0], [5.0, 6.0, 7.0, 8.0]];
+
+        let mut buf = Std140Bytes::new();
+        buf.write(&1.0f32);
+        buf.write_vec4_array_from_f32(&data);
+
+        let mut expected = Std140Bytes::new();
+        expected.write(&1.0f32);
+        expected.write_array(&data.map(Vec4::from_array));
+
+        assert_eq!(buf.as_slice(), expected.as_slice());
+
+        let le = data.as_flattened().iter().flat_map(|f| f.to_le_bytes());
+        assert!(buf.as_slice()[16..].iter().copied().eq(le));
+    }
+
+    #[test]
+    fn std140_compact() {
+        let mut buf = Std140Bytes::new();
+
+        buf.write(&0u32);
+        buf.align_to(16);
+        assert_eq!(buf.as_slice().len(), 16);
+
+        buf.compact();
+        assert_eq!(buf.as_slice(), &[0, 0, 0, 0]);
+        assert_eq!(buf.padding_bytes(), 0);
+    }
+
+    #[test]
+    fn integer_vec3_array_stride() {
+        use glam::IVec3;
+
+        let ivecs = vec![IVec3::splat(-1), IVec3::splat(-1)];
+        let uvecs = vec![UVec3::splat(u32::MAX), UVec3::splat(u32::MAX)];
+
+        #[rustfmt::skip]
+        let expected: &[u8] = &[
+            // element 0
+            u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+            u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+            u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+            // padding
+            0, 0, 0, 0,
+            // element 1
+            u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+            u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+            u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+            // padding
+            0, 0, 0, 0,
+        ];
+
+        assert_eq!(ivecs.as_std140().as_slice(), expected);
+        assert_eq!(ivecs.as_std430().as_slice(), expected);
+        assert_eq!(uvecs.as_std140().as_slice(), expected);
+        assert_eq!(uvecs.as_std430().as_slice(), expected);
+    }
+
+    #[test]
+    fn std140_fields_in_order() {
+        let mut buf = Std140Bytes::new();
+
+        buf.expect_fields(&["position", "radius"])
+            .write_field("position", &glam::Vec3::ZERO)
+            .write_field("radius", &1.0f32)
+            .finish_fields()
+            .align();
+
+        assert_eq!(buf.as_slice().len(), 16);
+    }
+
+    #[test]
+    #[should_panic(expected = "field `position` was expected next")]
+    fn std140_fields_out_of_order() {
+        Std140Bytes::new()
+            .expect_fields(&["position", "radius"])
+            .write_field("radius", &1.0f32);
+    }
+
+    #[test]
+    #[should_panic(expected = "field `radius` was never written")]
+    fn std140_fields_skipped() {
+        Std140Bytes::new()
+            .expect_fields(&["position", "radius"])
+            .write_field("position", &glam::Vec3::ZERO)
+            .finish_fields();
+    }
+
+    #[test]
+    fn std140_range_inclusive() {
+        let mut buf = Std140Bytes::new();
+        buf.write(&(0.0f32..=1.0));
+
+        let mut expected = Std140Bytes::new();
+        expected.write(&0.0f32).write(&1.0f32);
+
+        assert_eq!(buf.as_slice(), expected.as_slice());
+        assert_eq!(
+            (2u32..=7).as_std430().as_slice(),
+            bytemuck::cast_slice::<u32, u8>(&[2, 7])
+        );
+    }
+
+    #[test]
+    fn std140_write_mat() {
+        use glam::{Mat3, Vec3};
+
+        let mat = Mat3::from_cols_array(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0]);
+
+        let mut buf = Std140Bytes::new();
+        write_mat::<3, Vec3>(&mut buf, [mat.x_axis, mat.y_axis, mat.z_axis]);
+        buf.align();
+
+        let mut expected = Std140Bytes::new();
+        expected.write(&mat).align();
+
+        assert_eq!(buf.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn dmat2_size() {
+        use glam::DMat2;
+
+        // a dvec2 column is already 16 bytes, so both layouts agree
+        assert_eq!(DMat2::IDENTITY.as_std140().as_slice().len(), 32);
+        assert_eq!(DMat2::IDENTITY.as_std430().as_slice().len(), 32);
+        assert_eq!(
+            DMat2::IDENTITY.as_std140().as_slice(),
+            bytemuck::cast_slice::<f64, u8>(&[1.0, 0.0, 0.0, 1.0])
+        );
+    }
+
+    #[test]
+    fn metal_bool_is_one_byte() {
+        let mut buf = MetalBytes::new();
+
+        // in msl, `struct { bool a; bool b; float c; }` has `c` at offset 4
+        buf.write(&true).write(&false).write(&1.0f32);
+
+        assert_eq!(buf.as_slice()[..4], [1, 0, 0, 0]);
+        assert_eq!(buf.len(), 8);
+    }
+
+    #[test]
+    #[should_panic(expected = "use MetalBytes")]
+    fn gpu_buffer_rejects_metal() {
+        let _ = GpuBuffer::new(Layout::Metal);
+    }
+
+    #[test]
+    fn metal_vec3_and_scalar() {
+        let mut buf = MetalBytes::new();
+
+        buf.write(&UVec3::splat(u32::MAX));
+        buf.write(&u32::MAX);
+        buf.align();
+
+        // in msl, `struct { float3 a; float b; }` has `b` at offset 16 and a size of 32
+        #[rustfmt::skip]
+        assert_eq!(
+            buf.as_slice(),
+            &[
+                // x
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                // y
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                // z
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                // padding
+                0, 0, 0, 0,
+                // scalar
+                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                // padding
+                0, 0, 0, 0,
+                // padding
+                0, 0, 0, 0,
+                // padding
+                0, 0, 0, 0,
+            ]
+        );
+    }
+
+    #[test]
+    fn scalar_arrays() {
+        use glam::{IVec4, Vec4};
+
+        // 4-element arrays are laid out as arrays, so in std140 each element gets its own slot
+        let mut buf = Std140Bytes::new();
+        buf.write(&1u32).write(&[1u32, 2, 3, 4]);
+
+        let mut expected = Std140Bytes::new();
+        expected.write(&1u32).write_array(&[1u32, 2, 3, 4]);
+
+        assert_eq!(buf.as_slice(), expected.as_slice());
+        assert_eq!(buf.as_slice().len(), 80);
+
+        // in std430, their bytes are the same as the vec4 of the same scalar type
+        assert_eq!(
+            [-1i32, 2, -3, 4].as_std430().as_slice(),
+            IVec4::new(-1, 2, -3, 4).as_std430().as_slice()
+        );
+        assert_eq!(
+            [1.0f32, 2.0, 3.0, 4.0].as_std430().as_slice(),
+            Vec4::new(1.0, 2.0, 3.0, 4.0).as_std430().as_slice()
+        );
+    }
+
+    #[test]
+    fn scalar_arrays_as_vec4() {
+        use glam::{IVec4, UVec4, Vec4};
+
+        let mut buf = Std140Bytes::new();
+        buf.write(&1u32).write(&Vector4([1u32, 2, 3, 4]));
+
+        let mut expected = Std140Bytes::new();
+        expected.write(&1u32).write(&UVec4::new(1, 2, 3, 4));
+
+        assert_eq!(buf.as_slice(), expected.as_slice());
+        assert_eq!(
+            Vector4([-1i32, 2, -3, 4]).as_std430().as_slice(),
+            IVec4::new(-1, 2, -3, 4).as_std430().as_slice()
+        );
+        assert_eq!(
+            Vector4([1.0f32, 2.0, 3.0, 4.0]).as_std430().as_slice(),
+            Vec4::new(1.0, 2.0, 3.0, 4.0).as_std430().as_slice()
+        );
+    }
+
+    #[test]
+    fn std140_rewind_padding() {
+        let mut buf = Std140Bytes::new();
+
+        buf.write(&1u32).write(&2u32);
+        buf.align_to(16);
+        assert_eq!(buf.as_slice().len(), 16);
+
+        buf.rewind_padding();
+        assert_eq!(buf.as_slice().len(), 8);
+
+        // the padding can only be rewound once
+        buf.rewind_padding();
+        assert_eq!(buf.as_slice().len(), 8);
+
+        // and not at all after something else was written
+        buf.align_to(16).write(&3u32).rewind_padding();
+        assert_eq!(buf.as_slice().len(), 20);
+    }
+
+    #[test]
+    fn std140_transposed_matrices() {
+        use glam::Mat4;
+
+        let mat = Mat4::from_cols_array(&std::array::from_fn(|i| i as f32));
+
+        let mut buf = Std140Bytes::with_transposed_matrices(true);
+        buf.write(&1.0f32).write(&mat).write_array(&[mat]);
+
+        let mut expected = Std140Bytes::new();
+        expected
+            .write(&1.0f32)
+            .write(&mat.transpose())
+            .write_array(&[mat.transpose()]);
+
+        assert_ne!(mat, mat.transpose());
+        assert_eq!(buf.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn transposed_matrix_with_more_data_is_not_a_matrix() {
+        use glam::Mat3;
+
+        let mat = Mat3::from_cols_array(&std::array::from_fn(|i| i as f32));
+
+        // once more data is written after the matrix, the buffer is written as it is
+        let mut data = mat.as_std140();
+        data.write(&1.0f32);
+
+        let mut buf = Std140Bytes::with_transposed_matrices(true);
+        buf.write(&data);
+
+        assert_eq!(buf.len(), 52);
+        assert_eq!(buf.as_slice(), data.as_slice());
+
+        // std430 and Metal pad the columns of a mat3 too, which transposing leaves in place
+        let mut buf = Std430Bytes::with_transposed_matrices(true);
+        buf.write(&mat);
+        assert_eq!(buf.as_slice(), mat.transpose().as_std430().as_slice());
+
+        let mut buf = MetalBytes::with_transposed_matrices(true);
+        buf.write(&mat);
+        assert_eq!(buf.as_slice(), mat.transpose().as_metal().as_slice());
+    }
+
+    #[test]
+    fn std140_write_array_batch() {
+        use glam::{Vec2, Vec3};
+
+        let a = [Vec3::ONE, Vec3::ZERO];
+        let b = [Vec2::ONE];
+        let c = [Vec3::X, Vec3::Y, Vec3::Z];
+
+        let mut buf = Std140Bytes::new();
+        buf.write(&1.0f32).write_array_batch::<Vec3>(&[&a, &c]);
+        buf.write_array_batch(&[&b[..], &b[..]]);
+
+        let mut expected = Std140Bytes::new();
+        expected
+            .write(&1.0f32)
+            .write_array(&a)
+            .write_array(&c)
+            .write_array(&b)
+            .write_array(&b);
+
+        assert_eq!(buf.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn std140_sphere() {
+        use glam::{Vec3, Vec4};
+
+        let mut buf = Std140Bytes::new();
+        buf.write_sphere(Vec3::new(1.0, 2.0, 3.0), 0.5);
+
+        let mut expected = Std140Bytes::new();
+        expected.write(&Vec4::new(1.0, 2.0, 3.0, 0.5));
+
+        assert_eq!(buf.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn std140_from_reader() {
+        let bytes: Vec<u8> = (0..32).collect();
+
+        let buf = Std140Bytes::from_reader(&mut std::io::Cursor::new(&bytes), 16).unwrap();
+        assert_eq!(buf.as_slice(), &bytes[..]);
+
+        let buf = Std140Bytes::from_reader(&mut std::io::Cursor::new(&bytes[..20]), 16).unwrap();
+        assert_eq!(buf.as_slice(), &bytes[..20]);
+        assert_eq!(buf.alignment(), 16);
+    }
+
+    #[test]
+    fn write_array_mixed_alignment() {
+        let mut buf = Std140Bytes::new();
+
+        buf.write_array(&[
+            Std140Bytes::from_bytes(vec![1; 4], 4),
+            Std140Bytes::from_bytes(vec![2; 8], 8),
+        ]);
+
+        // each element is rounded up to 16 bytes on its own
+        #[rustfmt::skip]
+        assert_eq!(
+            buf.as_slice(),
+            &[
+                1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                2, 2, 2, 2, 2, 2, 2, 2, 0, 0, 0, 0, 0, 0, 0, 0,
+            ]
+        );
+
+        let mut buf = Std430Bytes::new();
+
+        buf.write_array(&[
+            Std430Bytes::from_bytes(vec![1; 4], 4),
+            Std430Bytes::from_bytes(vec![2; 8], 8),
+            Std430Bytes::from_bytes(vec![3; 4], 4),
+        ]);
+
+        // there's no common stride, each element is only aligned to its own alignment
+        #[rustfmt::skip]
+        assert_eq!(
+            buf.as_slice(),
+            &[
+                1, 1, 1, 1, 0, 0, 0, 0,
+                2, 2, 2, 2, 2, 2, 2, 2,
+                3, 3, 3, 3,
+            ]
+        );
+    }
+
+    #[test]
+    fn little_endian_scalars() {
+        // a big endian host would produce the big endian bytes, which get swapped
+        let mut bytes = 0x01020304u32.to_be_bytes();
+        swap_byte_order(&mut bytes, 4);
+        assert_eq!(bytes, 0x01020304u32.to_le_bytes());
+
+        assert_eq!(
+            0x01020304u32.as_std140().as_slice(),
+            &0x01020304u32.to_le_bytes()
+        );
+    }
+
+    #[test]
+    fn std140_vec2_array_packed() {
+        use glam::{Vec2, Vec4};
+
+        let mut buf = Std140Bytes::new();
+        buf.write_vec2_array_packed(&[
+            Vec2::new(1.0, 2.0),
+            Vec2::new(3.0, 4.0),
+            Vec2::new(5.0, 6.0),
+        ]);
+
+        let mut expected = Std140Bytes::new();
+        expected.write_array(&[Vec4::new(1.0, 2.0, 3.0, 4.0), Vec4::new(5.0, 6.0, 0.0, 0.0)]);
+
+        assert_eq!(buf.as_slice().len(), 32);
+        assert_eq!(buf.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn gpu_bytes_reset() {
+        let mut buf = GpuBytes::new(Layout::Std140);
+        buf.write_array([1u32, 2, 3].map(|e| e.as_std140().gpu_bytes));
+
+        let capacity = buf.bytes.capacity();
+        buf.reset(Layout::Std430);
+        buf.write_array([1u32, 2, 3].map(|e| e.as_std430().gpu_bytes));
+
+        let mut expected = GpuBytes::new(Layout::Std430);
+        expected.write_array([1u32, 2, 3].map(|e| e.as_std430().gpu_bytes));
+
+        assert_eq!(buf.layout, Layout::Std430);
+        assert_eq!(buf.as_slice(), expected.as_slice());
+        assert_eq!(buf.bytes.capacity(), capacity);
+    }
+
+    #[test]
+    fn std140_struct_array_stride() {
+        use glam::{Vec3, Vec4};
+
+        #[derive(Default)]
+        struct Sphere {
+            center: Vec3,
+            radius: f32,
+        }
+
+        impl AsStd140 for Sphere {
+            fn as_std140(&self) -> Std140Bytes {
+                let mut buf = Std140Bytes::new();
+                buf.write(&self.center).write(&self.radius).align();
+                buf
+            }
+        }
+
+        #[derive(Default)]
+        struct Tagged {
+            color: Vec4,
+            tag: u32,
+        }
+
+        impl AsStd140 for Tagged {
+            fn as_std140(&self) -> Std140Bytes {
+                let mut buf = Std140Bytes::new();
+                buf.write(&self.color).write(&self.tag).align();
+                buf
+            }
+        }
+
+        // 16 byte structs need no padding between elements
+        let spheres = vec![Sphere::default(), Sphere::default()];
+        assert_eq!(
+            Std140Bytes::new().write_array(&spheres).as_slice().len(),
+            32
+        );
+        assert_eq!(spheres.as_std140().as_slice().len(), 32);
+
+        // 20 byte structs are rounded up to a 32 byte stride
+        let tagged = vec![Tagged::default(), Tagged::default()];
+        assert_eq!(Std140Bytes::new().write_array(&tagged).as_slice().len(), 64);
+        assert_eq!(tagged.as_std140().as_slice().len(), 64);
+    }
+
+    #[test]
+    fn std140_align_reporting() {
+        let mut buf = Std140Bytes::new();
+
+        buf.write(&1u32);
+        assert_eq!(buf.align_to_reporting(16), 12);
+        assert_eq!(buf.align_reporting(), 0);
+    }
+
+    #[test]
+    fn mat2_column_stride() {
+        use glam::Mat2;
+
+        let mat = Mat2::from_cols_array(&[1.0, 2.0, 3.0, 4.0]);
+
+        // std140 rounds each vec2 column up to 16 bytes
+        assert_eq!(
+            mat.as_std140().as_slice(),
+            bytemuck::cast_slice::<f32, u8>(&[1.0, 2.0, 0.0, 0.0, 3.0, 4.0, 0.0, 0.0])
+        );
+
+        // std430 packs the columns tightly, at offsets 0 and 8
+        assert_eq!(
+            mat.as_std430().as_slice(),
+            bytemuck::cast_slice::<f32, u8>(&[1.0, 2.0, 3.0, 4.0])
+        );
+        assert_eq!(mat.as_std430().gpu_bytes.alignment, 8);
+    }
+
+    #[test]
+    fn std430_deduplicated() {
+        use glam::UVec2;
+
+        let a = UVec2::new(1, 2);
+        let b = UVec2::new(3, 4);
+
+        let mut buf = Std430Bytes::new();
+        let indices = buf.write_deduplicated(&[a, b, a, a, b]);
+
+        let mut expected = Std430Bytes::new();
+        expected.write_array(&[a, b]);
+
+        assert_eq!(indices, vec![0, 1, 0, 0, 1]);
+        assert_eq!(buf.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn std140_point_light() {
+        use glam::{Vec3, Vec4};
+
+        let mut buf = Std140Bytes::new();
+        buf.write_point_light(
+            Vec3::new(1.0, 2.0, 3.0),
+            10.0,
+            Vec3::new(1.0, 0.5, 0.0),
+            2.0,
+        );
+
+        let mut expected = Std140Bytes::new();
+        expected
+            .write(&Vec4::new(1.0, 2.0, 3.0, 10.0))
+            .write(&Vec4::new(1.0, 0.5, 0.0, 2.0));
+
+        assert_eq!(buf.as_slice().len(), 32);
+        assert_eq!(buf.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn std140_insert_at() {
+        let mut buf = Std140Bytes::new();
+
+        buf.write(&Vector4([1u32, 1, 1, 1]));
+        buf.insert_at(8, &[2; 4]);
+
+        #[rustfmt::skip]
+        assert_eq!(
+            buf.as_slice(),
+            &[
+                1, 0, 0, 0, 1, 0, 0, 0,
+                2, 2, 2, 2,
+                1, 0, 0, 0, 1, 0, 0, 0,
+            ]
+        );
+    }
+
+    #[test]
+    fn std140_affine3a_compact() {
+        use glam::{Affine3A, Quat, Vec3, Vec4};
+
+        let affine = Affine3A::from_scale_rotation_translation(
+            Vec3::new(1.0, 2.0, 3.0),
+            Quat::from_rotation_y(1.0),
+            Vec3::new(4.0, 5.0, 6.0),
+        );
+
+        let mut buf = Std140Bytes::new();
+        buf.write_affine3a_compact(affine);
+
+        assert_eq!(buf.as_slice().len(), 48);
+
+        // decode the same way the shader would, by dotting the point with each column
+        let columns: Vec<Vec4> = buf
+            .as_slice()
+            .chunks_exact(16)
+            .map(|column| Vec4::from_array(bytemuck::pod_read_unaligned(column)))
+            .collect();
+        let point = Vec3::new(-1.0, 0.5, 2.0).extend(1.0);
+        let decoded = Vec3::new(
+            point.dot(columns[0]),
+            point.dot(columns[1]),
+            point.dot(columns[2]),
+        );
+
+        assert!(decoded.abs_diff_eq(affine.transform_point3(point.truncate()), 1e-5));
+    }
+
+    #[test]
+    fn std140_write_array_capped() {
+        let mut buf = Std140Bytes::new();
+        buf.write_array_capped(0..10u32, 4);
+
+        let mut expected = Std140Bytes::new();
+        expected.write_array(&[0u32, 1, 2, 3]);
+
+        assert_eq!(buf.as_slice(), expected.as_slice());
+
+        let mut buf = Std140Bytes::new();
+        buf.write_array_capped([1u32, 2], 4);
+
+        let mut expected = Std140Bytes::new();
+        expected.write_array(&[1u32, 2, 0, 0]);
+
+        assert_eq!(buf.as_slice(), expected.as_slice());
+        assert_eq!(buf.padding_bytes(), 2 * 12 + 2 * 16);
+    }
+
+    #[test]
+    fn std140_tuple_of_arrays() {
+        use glam::{Vec3, Vec4};
+
+        let positions = [Vec3::X, Vec3::Y, Vec3::Z];
+        let colors = [Vec4::ONE, Vec4::W];
+
+        let mut buf = Std140Bytes::new();
+        buf.write(&1.0f32).write(&(&positions[..], &colors[..]));
+
+        let mut expected = Std140Bytes::new();
+        expected
+            .write(&1.0f32)
+            .write_array(&positions)
+            .write_array(&colors);
+
+        assert_eq!(buf.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn std140_align_to_within() {
+        let mut buf = Std140Bytes::new();
+        buf.write(&glam::Vec3::ONE).write(&1.0f32).write(&1.0f32);
+
+        assert_eq!(
+            buf.align_to_within(16, 24),
+            Err(GpuBytesError::ExceedsMaxSize {
+                size: 32,
+                max_size: 24
+            })
+        );
+        assert_eq!(buf.as_slice().len(), 20);
+
+        assert_eq!(buf.align_to_within(16, 32), Ok(()));
+        assert_eq!(buf.as_slice().len(), 32);
+    }
+
+    #[test]
+    fn std430_array_length_exact() {
+        use glam::Vec3;
+
+        let mut data = Vec::with_capacity(8);
+        data.extend([Vec3::X, Vec3::Y, Vec3::Z]);
+
+        let stride = 16;
+        let exact = data.as_std430_array_length_exact();
 
-primitive_impl_std140_std430!(i32, align = 4);
-primitive_impl_std140_std430!(glam::IVec2, align = 8);
-primitive_impl_std140_std430!(glam::IVec3, align = 16);
-primitive_impl_std140_std430!(glam::IVec4, align = 16);
+        assert_eq!(exact.as_slice().len() / stride, data.len());
+        assert_eq!(data.as_std430().as_slice().len() / stride, data.capacity());
+    }
+
+    #[test]
+    fn matrix_shader_size() {
+        use glam::{Mat2, Mat3, Mat4};
+
+        assert_eq!(Mat3::shader_size(Layout::Std140), 48);
+        assert_eq!(Mat4::shader_size(Layout::Std140), 64);
+        assert_eq!(Mat3::shader_size(Layout::Std430), 48);
+        assert_eq!(Mat2::shader_size(Layout::Std140), 32);
+        assert_eq!(Mat2::shader_size(Layout::Std430), 16);
+    }
+
+    #[test]
+    fn std140_write_gpu_bytes() {
+        let mut buf = Std140Bytes::new();
+
+        assert_eq!(buf.write_gpu_bytes(&1u32.as_std140().gpu_bytes), Ok(()));
+        assert_eq!(
+            buf.write_gpu_bytes(&1u32.as_std430().gpu_bytes),
+            Err(GpuBytesError::LayoutMismatch {
+                expected: Layout::Std140,
+                actual: Layout::Std430
+            })
+        );
+        assert_eq!(buf.as_slice(), &[1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn std140_bone_data() {
+        use glam::{UVec4, Vec4};
+
+        let mut buf = Std140Bytes::new();
+        buf.write_bone_data([0, 1, 2, 3], [0.5, 0.25, 0.25, 0.0]);
+
+        let mut expected = Std140Bytes::new();
+        expected
+            .write(&UVec4::new(0, 1, 2, 3))
+            .write(&Vec4::new(0.5, 0.25, 0.25, 0.0));
+
+        assert_eq!(buf.as_slice().len(), 32);
+        assert_eq!(buf.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn std140_append_all() {
+        let blocks: Vec<Std140Bytes> = (0..100u32)
+            .map(|i| {
+                let mut block = Std140Bytes::new();
+                block.write(&i);
+
+                if i % 3 == 0 {
+                    block.write(&glam::Vec3::splat(i as f32));
+                }
+
+                block
+            })
+            .collect();
+
+        let mut buf = Std140Bytes::new();
+        buf.append_all(&blocks);
+
+        let mut expected = Std140Bytes::new();
+
+        for block in blocks.iter() {
+            expected.append(block);
+        }
+
+        assert_eq!(buf.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn fixed16() {
+        assert_eq!(Fixed16::<8>(1.5).to_bits(), 0x0180);
+        assert_eq!(Fixed16::<8>(1.5).as_std140().as_slice(), &[0x80, 0x01]);
+        assert_eq!(
+            Fixed16::<8>(-1.0).as_std430().as_slice(),
+            &(-256i16).to_le_bytes()
+        );
+
+        assert_eq!(Fixed16::<0>(3.4).to_bits(), 3);
+        assert_eq!(Fixed16::<0>(32767.0).to_bits(), i16::MAX);
+        assert_eq!(Fixed16::<15>(0.5).to_bits(), 0x4000);
+        assert_eq!(Fixed16::<15>(-1.0).to_bits(), i16::MIN);
+
+        // values out of range saturate
+        assert_eq!(Fixed16::<15>(1.0).to_bits(), i16::MAX);
+        assert_eq!(Fixed16::<0>(40000.0).to_bits(), i16::MAX);
+        assert_eq!(Fixed16::<8>(-200.0).to_bits(), i16::MIN);
+    }
+
+    #[test]
+    fn std140_snapshot_restore() {
+        let mut buf = Std140Bytes::new();
+        buf.write(&1u32);
+
+        let snapshot = buf.snapshot();
+        buf.write(&glam::Vec4::ONE).align();
+        assert_eq!(buf.as_slice().len(), 32);
+
+        buf.restore(snapshot);
+        assert_eq!(snapshot.len(), 4);
+        assert_eq!(snapshot.alignment(), 4);
+        assert_eq!(buf.snapshot(), snapshot);
+        assert_eq!(buf.as_slice(), &[1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn array_element_alignment() {
+        // a vec2 element
+        assert_eq!(Layout::Std140.array_element_alignment(8), 16);
+        assert_eq!(Layout::Std430.array_element_alignment(8), 8);
+        assert_eq!(Layout::Metal.array_element_alignment(8), 8);
+    }
+
+    #[test]
+    fn std140_viewport() {
+        let mut buf = Std140Bytes::new();
+        buf.write_viewport(0, 10, 1920, 1080);
+
+        let mut expected = Std140Bytes::new();
+        expected.write(&glam::UVec4::new(0, 10, 1920, 1080));
+
+        assert_eq!(buf.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn std140_write_array_offsets() {
+        let mut buf = Std140Bytes::new();
+
+        assert_eq!(buf.write_array_offsets(&[1u32, 2, 3]), vec![0, 16, 32]);
+        assert_eq!(buf.write(&1u32).write_array_offsets(&[1u32]), vec![64]);
+    }
+
+    #[test]
+    fn bool_array_bitfield() {
+        assert_eq!(
+            [true, false, true].as_std140().as_slice(),
+            &0b101u32.to_le_bytes()
+        );
+
+        let mut flags = [false; 33];
+        flags[0] = true;
+        flags[32] = true;
+
+        assert_eq!(
+            flags.as_std430().as_slice(),
+            bytemuck::cast_slice::<u32, u8>(&[1, 1])
+        );
+        assert_eq!(
+            flags.as_std430().alignment(),
+            <[bool; 33] as ShaderAlignment>::STD430_ALIGNMENT
+        );
+        assert_eq!([true; 32].as_std140().as_slice(), &u32::MAX.to_le_bytes());
+    }
+
+    #[test]
+    fn std140_padding_warnings() {
+        use glam::Vec3;
+
+        let mut buf = Std140Bytes::with_padding_warnings(true);
+        buf.write(&Vec3::ONE).write(&Vec3::ONE);
+        buf.write_array(&[Vec3::ONE, Vec3::ONE]);
+
+        assert_eq!(
+            buf.padding_warnings(),
+            &[PaddingWarning {
+                offset: 12,
+                padding: 4
+            }]
+        );
+
+        assert_eq!(buf.take_padding_warnings().len(), 1);
+        assert!(buf.padding_warnings().is_empty());
+
+        buf.write(&1.0f32).write(&Vec3::ONE);
+        assert_eq!(buf.padding_warnings().len(), 1);
+
+        let mut buf = Std140Bytes::new();
+        buf.write(&Vec3::ONE).write(&Vec3::ONE);
+
+        assert!(buf.padding_warnings().is_empty());
+        assert!(buf.take_padding_warnings().is_empty());
+    }
+
+    #[test]
+    fn ipv4_addr() {
+        let addr = std::net::Ipv4Addr::new(127, 0, 0, 1);
+
+        assert_eq!(addr.as_std140().as_slice(), &0x7f000001u32.to_le_bytes());
+    }
+
+    #[test]
+    fn vec_try_as_std140() {
+        let empty: Vec<u32> = Vec::new();
+        assert_eq!(
+            empty.try_as_std140().unwrap_err(),
+            GpuBytesError::ZeroCapacityVec
+        );
+        assert_eq!(
+            empty.try_as_std430().unwrap_err(),
+            GpuBytesError::ZeroCapacityVec
+        );
+
+        // a vec with capacity but no elements is all padding
+        let reserved: Vec<u32> = Vec::with_capacity(2);
+        assert_eq!(reserved.try_as_std140().unwrap().as_slice(), &[0; 32]);
+        assert_eq!(reserved.try_as_std430().unwrap().as_slice(), &[0; 8]);
+    }
+
+    #[test]
+    #[should_panic(expected = "should have an initial capacity")]
+    fn vec_as_std140_zero_capacity() {
+        Vec::<u32>::new().as_std140();
+    }
+
+    #[test]
+    fn std140_write_dyn() {
+        use glam::{Vec3, Vec4};
+
+        let values: Vec<Box<dyn AsStd140>> =
+            vec![Box::new(1.0f32), Box::new(Vec3::ONE), Box::new(Vec4::ONE)];
+
+        let mut buf = Std140Bytes::new();
+
+        for value in values.iter() {
+            buf.write_dyn(value.as_ref());
+        }
+
+        let mut expected = Std140Bytes::new();
+        expected.write(&1.0f32).write(&Vec3::ONE).write(&Vec4::ONE);
+
+        assert_eq!(buf.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn std140_decomposed_transform() {
+        use glam::{Quat, Vec3};
+
+        let rotation = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+
+        let mut buf = Std140Bytes::new();
+        buf.write_decomposed_transform(Vec3::new(1.0, 2.0, 3.0), rotation, Vec3::splat(2.0));
+
+        let floats: &[f32] = bytemuck::cast_slice(buf.as_slice());
+
+        assert_eq!(floats.len(), 12);
+        assert_eq!(floats[0..4], rotation.to_array());
+        assert_eq!(floats[4..8], [1.0, 2.0, 3.0, 0.0]);
+        assert_eq!(floats[8..12], [2.0, 2.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn dvec3_followed_by_f64() {
+        use glam::{DVec3, DVec4};
+
+        let mut buf = Std430Bytes::new();
+        buf.write(&1.0f32).write(&DVec3::ONE).write(&2.0f64);
+
+        // the dvec3 is aligned to 32, and the f64 fits in its trailing 8 bytes
+        assert_eq!(buf.padding_bytes(), 28);
+        assert_eq!(buf.as_slice()[56..64], 2.0f64.to_le_bytes());
+
+        buf.align();
+        assert_eq!(buf.as_slice().len(), 64);
+
+        assert_eq!(DVec4::ONE.as_std140().as_slice().len(), 32);
+    }
+
+    #[test]
+    fn fixed_size_array_stride() {
+        assert_eq!([1u32, 2, 3].as_std140().as_slice().len(), 48);
+        assert_eq!([1u32, 2, 3].as_std430().as_slice().len(), 12);
+
+        assert_eq!(
+            [1u32, 2, 3].as_std140().as_slice(),
+            (&[1u32, 2, 3][..]).as_std140().as_slice()
+        );
+        assert_eq!(
+            [1u32, 2, 3].as_std430().as_slice(),
+            bytemuck::cast_slice::<u32, u8>(&[1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn std140_alignment_and_len() {
+        let mut buf = Std140Bytes::new();
+
+        assert!(buf.is_empty());
+        assert_eq!(buf.alignment(), 0);
+
+        buf.write(&1.0f32).write(&glam::Vec2::ONE);
+
+        assert_eq!(buf.alignment(), 8);
+        assert_eq!(buf.len(), 16);
+        assert!(!buf.is_empty());
+
+        buf.write(&glam::Vec3::ONE);
+        assert_eq!(buf.alignment(), 16);
+        assert_eq!(buf.len(), buf.as_slice().len());
+    }
+
+    #[test]
+    fn std140_write_struct_sized() {
+        use glam::Vec4;
+
+        let mut buf = Std140Bytes::new();
+        buf.write_struct_sized(&Vec4::ONE, 32).write(&2.0f32);
+
+        let mut expected = Std140Bytes::new();
+        expected.write(&Vec4::ONE).write(&Vec4::ZERO).write(&2.0f32);
+
+        assert_eq!(buf.as_slice(), expected.as_slice());
+        assert_eq!(buf.padding_bytes(), 16);
+    }
+
+    #[test]
+    fn bool_as_u32() {
+        assert_eq!(true.as_std140().as_slice(), &[1, 0, 0, 0]);
+        assert_eq!(false.as_std430().as_slice(), &[0, 0, 0, 0]);
+
+        // only fixed-size arrays are bit packed
+        assert_eq!([true, false].as_std430().as_slice().len(), 4);
+        assert_eq!((&[true, false][..]).as_std430().as_slice().len(), 8);
+    }
+
+    #[test]
+    fn std140_transform2d() {
+        let mut buf = Std140Bytes::new();
+        buf.write_transform2d(std::f32::consts::FRAC_PI_2, glam::Vec2::new(3.0, 4.0));
+
+        let floats: &[f32] = bytemuck::cast_slice(buf.as_slice());
+
+        assert_eq!(floats.len(), 4);
+        assert!(floats[0].abs() < 1e-6);
+        assert_eq!(floats[1..], [1.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn std140_write_array_with_stride() {
+        let mut buf = Std140Bytes::new();
+        buf.write_array_with_stride(&[1.0f32, 2.0], 32).unwrap();
+
+        let mut expected = [0.0f32; 16];
+        expected[0] = 1.0;
+        expected[8] = 2.0;
+
+        assert_eq!(buf.as_slice(), bytemuck::cast_slice::<f32, u8>(&expected));
+
+        assert_eq!(
+            buf.write_array_with_stride(&[glam::Vec4::ONE], 8),
+            Err(GpuBytesError::InvalidStride {
+                stride: 8,
+                size: 16,
+                alignment: 16
+            })
+        );
+        assert_eq!(buf.as_slice().len(), 64);
+    }
+
+    #[test]
+    fn std430_write_ragged() {
+        let mut buf = Std430Bytes::new();
+        let table = buf.write_ragged(&[vec![1u32, 2, 3], vec![], vec![4]]);
+
+        assert_eq!(table, vec![(0, 3), (3, 0), (3, 1)]);
+        assert_eq!(
+            buf.as_slice(),
+            bytemuck::cast_slice::<u32, u8>(&[1, 2, 3, 4])
+        );
+    }
+
+    #[test]
+    fn std140_clear() {
+        use glam::{Vec3, Vec4};
+
+        let mut buf = Std140Bytes::new();
+        buf.write(&Vec4::ONE).write(&1.0f32).align();
+
+        let capacity = buf.gpu_bytes.bytes.capacity();
+        buf.clear();
+
+        assert!(buf.is_empty());
+        assert_eq!(buf.gpu_bytes.bytes.capacity(), capacity);
+        assert_eq!(buf.gpu_bytes.layout(), Layout::Std140);
+
+        buf.write(&1u32).write(&Vec3::ONE);
+
+        let mut expected = Std140Bytes::new();
+        expected.write(&1u32).write(&Vec3::ONE);
+
+        assert_eq!(buf.as_slice(), expected.as_slice());
+        assert_eq!(buf.alignment(), expected.alignment());
+        assert_eq!(buf.padding_bytes(), expected.padding_bytes());
+    }
+
+    #[test]
+    fn std140_write_try() {
+        fn build(third: Result<&f32, &'static str>) -> Result<Std140Bytes, &'static str> {
+            let mut buf = Std140Bytes::new();
+            buf.write_try(Ok(&1.0f32))?
+                .write_try(Ok(&2.0f32))?
+                .write_try(third)?;
+
+            Ok(buf)
+        }
+
+        assert_eq!(build(Ok(&3.0)).unwrap().as_slice().len(), 12);
+        assert_eq!(build(Err("missing")).unwrap_err(), "missing");
+    }
+
+    #[test]
+    fn std140_pbr_params() {
+        use glam::Vec4;
+
+        let mut buf = Std140Bytes::new();
+        buf.write_pbr_params(Vec4::new(1.0, 0.5, 0.25, 1.0), 0.1, 0.2, 0.3, 0.4);
+
+        assert_eq!(
+            buf.as_slice(),
+            bytemuck::cast_slice::<f32, u8>(&[1.0, 0.5, 0.25, 1.0, 0.1, 0.2, 0.3, 0.4])
+        );
+    }
+
+    #[test]
+    fn u64_alignment() {
+        assert_eq!(u64::MAX.as_std140().as_slice(), &[0xff; 8]);
+        assert_eq!((-2i64).as_std430().as_slice(), &(-2i64).to_le_bytes());
+
+        let mut buf = Std430Bytes::new();
+        buf.write(&1u32).write(&u64::MAX);
+
+        assert_eq!(buf.padding_bytes(), 4);
+        assert_eq!(buf.as_slice()[8..], [0xff; 8]);
+    }
+
+    #[test]
+    fn shader_alignment_constants() {
+        use glam::{Mat2, Mat3, Vec2, Vec3};
+
+        const VEC2_ALIGNMENT: usize = <Vec2 as ShaderAlignment>::STD140_ALIGNMENT;
+        assert_eq!(VEC2_ALIGNMENT, 8);
+
+        assert_eq!(<Vec3 as ShaderAlignment>::STD430_ALIGNMENT, 16);
+        assert_eq!(<Mat2 as ShaderAlignment>::STD140_ALIGNMENT, 16);
+        assert_eq!(<Mat2 as ShaderAlignment>::STD430_ALIGNMENT, 8);
+        assert_eq!(<[f32; 3] as ShaderAlignment>::STD140_ALIGNMENT, 16);
+        assert_eq!(<[f32; 3] as ShaderAlignment>::STD430_ALIGNMENT, 4);
+
+        // the constants agree with the alignment of the written bytes
+        assert_eq!(
+            <Mat3 as ShaderAlignment>::STD430_ALIGNMENT,
+            Mat3::IDENTITY.as_std430().alignment()
+        );
+        assert_eq!(
+            <[Vec2; 2] as ShaderAlignment>::STD140_ALIGNMENT,
+            [Vec2::ONE; 2].as_std140().alignment()
+        );
+    }
+
+    #[test]
+    fn std140_array_after_scalar() {
+        use glam::Vec4;
+
+        let mut buf = Std140Bytes::new();
+        buf.write(&1.0f32).write_array(&[Vec4::ONE; 2]);
+
+        // the destination is padded to the array's alignment once, so the first element starts
+        // at 16 rather than 4, and no padding is counted twice
+        assert_eq!(buf.as_slice().len(), 48);
+        assert_eq!(buf.padding_bytes(), 12);
+        assert_eq!(
+            buf.as_slice()[16..],
+            *bytemuck::cast_slice::<f32, u8>(&[1.0; 8])
+        );
+
+        let mut offsets = Std140Bytes::new();
+        offsets.write(&1.0f32);
+
+        assert_eq!(offsets.write_array_offsets(&[Vec4::ONE; 2]), vec![16, 32]);
+    }
+
+    #[test]
+    fn std140_write_array_with_stride_after_scalar() {
+        use glam::Vec2;
+
+        let mut buf = Std140Bytes::with_debug_offsets();
+        buf.write(&1.0f32)
+            .write_array_with_stride(&[Vec2::ONE], 16)
+            .unwrap();
+
+        // the array starts at the std140 array alignment of 16, like with `write_array`
+        let mut expected = Std140Bytes::new();
+        expected.write(&1.0f32).write_array(&[Vec2::ONE]);
+
+        assert_eq!(buf.as_slice(), expected.as_slice());
+        assert_eq!(buf.alignment(), 16);
+        assert_eq!(
+            buf.offsets()[1],
+            FieldOffset {
+                offset: 16,
+                size: 16,
+                alignment: 16
+            }
+        );
+    }
+
+    #[test]
+    fn std140_stride_below_natural_stride() {
+        use glam::Vec4;
+
+        let mut buf = Std140Bytes::new();
+        buf.write_array_with_stride(&[Vec4::ONE, Vec4::ONE], 32)
+            .unwrap();
+
+        assert_eq!(buf.as_slice().len(), 64);
+        assert_eq!(buf.as_slice()[16..32], [0; 16]);
+
+        // an f32 is only 4 bytes, but std140 array elements take at least 16
+        assert_eq!(
+            buf.write_array_with_stride(&[1.0f32], 8),
+            Err(GpuBytesError::InvalidStride {
+                stride: 8,
+                size: 4,
+                alignment: 4
+            })
+        );
+        assert!(Std430Bytes::new()
+            .write_array_with_stride(&[1.0f32], 8)
+            .is_ok());
+    }
+
+    #[test]
+    fn std140_write_array_iter() {
+        use glam::Vec3;
+
+        let mut buf = Std140Bytes::new();
+        buf.write(&1u32)
+            .write_array_iter((0..3).map(|i| Vec3::splat(i as f32)));
+
+        let mut expected = Std140Bytes::new();
+        expected
+            .write(&1u32)
+            .write_array(&[Vec3::ZERO, Vec3::ONE, Vec3::splat(2.0)]);
+
+        assert_eq!(buf.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn complex_as_vec2() {
+        use glam::Vec2;
+
+        let mut buf = Std430Bytes::new();
+        buf.write(&1.0f32)
+            .write_complex(2.0, -3.0)
+            .write(&Complex::new(4.0, 5.0));
 
-primitive_impl_std140_std430!(u32, align = 4);
-primitive_impl_std140_std430!(glam::UVec2, align = 8);
-primitive_impl_std140_std430!(glam::UVec3, align = 16);
-primitive_impl_std140_std430!(glam::UVec4, align = 16);
+        let mut expected = Std430Bytes::new();
+        expected
+            .write(&1.0f32)
+            .write(&Vec2::new(2.0, -3.0))
+            .write(&Vec2::new(4.0, 5.0));
 
-primitive_impl_std140_std430_matrix!(glam::Mat3, columns = 3);
-primitive_impl_std140_std430_matrix!(glam::Mat4, columns = 4);
+        assert_eq!(buf.as_slice(), expected.as_slice());
+    }
 
-impl<T: AsStd140 + Default> AsStd140 for Vec<T> {
-    fn as_std140(&self) -> Std140Bytes {
-        let mut buf = Std140Bytes::new();
+    #[test]
+    fn std430_vec_of_structs_alignment() {
+        use glam::{Vec2, Vec4};
 
-        if self.capacity() == 0 {
-            panic!("A Vec<T> should have an initial capacity before being converted to gpu layout");
-        }
+        let elems = vec![(Vec2::ONE, Vec4::ONE); 2];
 
-        let mut std140 = T::default().as_std140();
+        let std430 = elems.as_std430();
 
-        // in std140, array elements are aligned to a multiple of 16
-        std140.align_to(16);
+        // the vec4 member puts the struct at 16-byte alignment, so each element takes 32 bytes
+        assert_eq!(std430.alignment(), 16);
+        assert_eq!(std430.as_slice().len(), 64);
+        assert_eq!(std430.as_slice()[32..40], std430.as_slice()[..8]);
 
-        let bytes_per_element = std140.as_slice().len();
+        assert_eq!(elems.as_std140().alignment(), 16);
+    }
 
-        // the gpu representation will contain as many bytes as possible to hold the vec's capacity
-        // and fill the appropriate number of bytes with the vec's elements
-        let total_bytes = bytes_per_element * self.capacity();
+    #[test]
+    fn from_std430_round_trip() {
+        use glam::{Mat3, Mat4, Vec3, Vec4};
 
-        for elem in self.iter() {
-            let mut std140 = elem.as_std140();
+        let v = Vec4::new(1.0, 2.0, 3.0, 4.0);
+        assert_eq!(Vec4::from_std430(v.as_std430().as_slice()), Ok(v));
 
-            // in std140, array elements are aligned to a multiple of 16
-            std140.align_to(16);
+        let mat = Mat4::from_cols_array(&std::array::from_fn(|i| i as f32));
+        assert_eq!(Mat4::from_std430(mat.as_std430().as_slice()), Ok(mat));
 
-            buf.gpu_bytes.bytes.extend_from_slice(std140.as_slice());
-        }
+        // mat3 columns are padded to 16 bytes
+        let mat = Mat3::from_cols_array(&std::array::from_fn(|i| i as f32));
+        assert_eq!(Mat3::from_std430(mat.as_std430().as_slice()), Ok(mat));
 
-        // now pad with 0's for the remaining capacity
-        let padding = total_bytes - buf.gpu_bytes.bytes.len();
+        assert_eq!(
+            Vec4::from_std430(&[0; 8]),
+            Err(GpuBytesError::TooShort { len: 8, needed: 16 })
+        );
 
-        buf.gpu_bytes
-            .bytes
-            .extend(std::iter::repeat(0u8).take(padding));
+        let mut buf = Std430Bytes::new();
+        buf.write(&1u32)
+            .write(&Vec3::ONE)
+            .write(&2.0f32)
+            .write(&mat);
 
-        buf.gpu_bytes.alignment = 16;
+        let mut reader = Std430Reader::new(buf.as_slice());
 
-        buf
+        assert_eq!(reader.read::<u32>(), Ok(1));
+        assert_eq!(reader.read::<Vec3>(), Ok(Vec3::ONE));
+        assert_eq!(reader.read::<f32>(), Ok(2.0));
+        assert_eq!(reader.read::<Mat3>(), Ok(mat));
+        assert_eq!(reader.offset(), buf.as_slice().len());
     }
-}
 
-impl<T: AsStd430 + Default> AsStd430 for Vec<T> {
-    fn as_std430(&self) -> Std430Bytes {
-        let mut buf = Std430Bytes::new();
+    #[test]
+    fn write_align_matches_single_element_array() {
+        use glam::*;
 
-        if self.capacity() == 0 {
-            panic!("A Vec<T> should have an initial capacity before being converted to gpu layout");
+        macro_rules! check {
+            (metal: $($value:expr),+ $(,)?) => {$({
+                let value = $value;
+
+                let mut written = MetalBytes::new();
+                written.write(&value).align();
+                let mut array = MetalBytes::new();
+                array.write_array(&[value]);
+                assert_eq!(written.as_slice(), array.as_slice(), "metal {:?}", value);
+            })+};
+            ($($value:expr),+ $(,)?) => {$({
+                let value = $value;
+
+                let mut written = Std430Bytes::new();
+                written.write(&value).align();
+                let mut array = Std430Bytes::new();
+                array.write_array(&[value]);
+                assert_eq!(written.as_slice(), array.as_slice(), "std430 {:?}", value);
+
+                // std140 additionally rounds array elements up to 16 bytes
+                let mut written = Std140Bytes::new();
+                written.write(&value);
+                let align = Layout::Std140.array_element_alignment(written.alignment());
+                written.align_to(align);
+                let mut array = Std140Bytes::new();
+                array.write_array(&[value]);
+                assert_eq!(written.as_slice(), array.as_slice(), "std140 {:?}", value);
+            })+};
         }
 
-        let mut std430 = T::default().as_std430();
-        std430.align();
+        check!(
+            1.0f32,
+            Vec2::ONE,
+            Vec3::ONE,
+            Vec4::ONE,
+            1i32,
+            IVec2::ONE,
+            IVec3::ONE,
+            IVec4::ONE,
+            1u32,
+            UVec2::ONE,
+            UVec3::ONE,
+            UVec4::ONE,
+            1u64,
+            1i64,
+            1.0f64,
+            DVec2::ONE,
+            DVec3::ONE,
+            DVec4::ONE,
+            Mat2::IDENTITY,
+            Mat3::IDENTITY,
+            Mat4::IDENTITY,
+            DMat2::IDENTITY,
+            true,
+            Fixed16::<8>(1.5),
+            Complex::new(1.0, 2.0),
+        );
 
-        let bytes_per_element = std430.as_slice().len();
+        // MSL has no 64-bit floats, and a single bool is one byte while bool arrays are bit packed
+        check!(
+            metal: 1.0f32,
+            Vec2::ONE,
+            Vec3::ONE,
+            Vec4::ONE,
+            1i32,
+            IVec2::ONE,
+            IVec3::ONE,
+            IVec4::ONE,
+            1u32,
+            UVec2::ONE,
+            UVec3::ONE,
+            UVec4::ONE,
+            1u64,
+            1i64,
+            Mat2::IDENTITY,
+            Mat3::IDENTITY,
+            Mat4::IDENTITY,
+            Fixed16::<8>(1.5),
+            Complex::new(1.0, 2.0),
+        );
+    }
 
-        // the gpu representation will contain as many bytes as possible to hold the vec's capacity
-        // and fill the appropriate number of bytes with the vec's elements
-        let total_bytes = bytes_per_element * self.capacity();
+    #[test]
+    fn quat_as_vec4() {
+        use glam::{Quat, Vec4};
 
-        for elem in self.iter() {
-            let mut std430 = elem.as_std430();
-            std430.align();
+        assert_eq!(
+            Quat::IDENTITY.as_std140().as_slice(),
+            bytemuck::cast_slice::<f32, u8>(&[0.0, 0.0, 0.0, 1.0])
+        );
 
-            buf.gpu_bytes.bytes.extend_from_slice(std430.as_slice());
-        }
+        let q = Quat::from_rotation_y(1.0);
 
-        // now pad with 0's for the remaining capacity
-        let padding = total_bytes - buf.gpu_bytes.bytes.len();
+        assert_eq!(
+            q.as_std430().as_slice(),
+            Vec4::from(q).as_std430().as_slice()
+        );
+        assert_eq!(Quat::from_std430(q.as_std430().as_slice()), Ok(q));
+    }
 
-        buf.gpu_bytes
-            .bytes
-            .extend(std::iter::repeat(0u8).take(padding));
+    #[test]
+    fn duration_as_seconds() {
+        use std::time::Duration;
+
+        let mut buf = Std140Bytes::new();
+        buf.write(&Duration::from_millis(500));
 
-        // the alignment of the array is the same as the alignment of the elements in std430
-        buf.gpu_bytes.alignment = std430.gpu_bytes.alignment;
+        assert_eq!(buf.as_slice(), &0.5f32.to_le_bytes());
 
-        buf
+        let mut buf = Std140Bytes::new();
+        buf.write_duration_precise(Duration::new(3, 250));
+
+        assert_eq!(buf.as_slice(), bytemuck::cast_slice::<u32, u8>(&[3, 250]));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use glam::UVec3;
+    #[test]
+    fn std140_append_realigns() {
+        use glam::{Vec3, Vec4};
 
-    use super::*;
+        let mut first = Std140Bytes::new();
+        first.write(&Vec4::ONE).write(&Vec3::ONE);
+
+        let mut second = Std140Bytes::new();
+        second.write(&Vec4::splat(2.0)).write(&1.0f32);
+
+        let padding = first.padding_bytes();
+        first.append(&second);
+
+        // the vec3 leaves the buffer 4 bytes short of the vec4's alignment
+        assert_eq!(first.padding_bytes() - padding, 4);
+        assert_eq!(first.as_slice()[28..32], [0; 4]);
+        assert_eq!(
+            first.as_slice()[32..48],
+            *Vec4::splat(2.0).as_std140().as_slice()
+        );
+        assert_eq!(first.alignment(), 16);
+    }
 
     #[test]
-    fn std140_vec3_and_scalar() {
+    fn packed_unorm4x8() {
+        let packed = PackedUnorm4x8::from_vec4(glam::Vec4::new(1.0, 0.0, 0.5, 1.0));
+
+        assert_eq!(packed.0, 0xff80_00ff);
+        assert_eq!(packed.as_std140().as_slice(), &0xff80_00ffu32.to_le_bytes());
+        assert_eq!(
+            PackedUnorm4x8::from_vec4(glam::Vec4::new(-1.0, 2.0, 0.0, 0.0)).0,
+            0x0000_ff00
+        );
+    }
+
+    #[test]
+    fn std140_finish_expecting() {
         let mut buf = Std140Bytes::new();
+        buf.write(&glam::Vec3::ONE).write(&1.0f32);
 
-        buf.write(&UVec3::splat(u32::MAX));
-        buf.write(&u32::MAX);
-        buf.align();
+        assert_eq!(buf.finish_expecting(16), Ok(buf.as_slice()));
+        assert_eq!(
+            buf.finish_expecting(32),
+            Err(GpuBytesError::SizeMismatch {
+                expected: 32,
+                actual: 16
+            })
+        );
+    }
+
+    #[test]
+    fn std140_debug_offsets() {
+        use glam::{Vec3, Vec4};
+
+        let mut buf = Std140Bytes::with_debug_offsets();
+        buf.write(&Vec3::ONE).write(&1.0f32).write(&Vec4::ONE);
 
-        #[rustfmt::skip]
         assert_eq!(
-            buf.as_slice(),
+            buf.offsets(),
             &[
-                // x
-                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
-                // y
-                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
-                // z
-                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
-                // scalar
-                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
+                FieldOffset {
+                    offset: 0,
+                    size: 12,
+                    alignment: 16
+                },
+                FieldOffset {
+                    offset: 12,
+                    size: 4,
+                    alignment: 4
+                },
+                FieldOffset {
+                    offset: 16,
+                    size: 16,
+                    alignment: 16
+                },
             ]
         );
+        assert!(Std140Bytes::new().write(&1.0f32).offsets().is_empty());
     }
 
     #[test]
-    fn std140_vec3_and_vec3() {
-        let mut buf = Std140Bytes::new();
+    fn std140_debug_offsets_restore() {
+        use glam::Vec4;
 
-        buf.write(&UVec3::splat(u32::MAX));
-        buf.write(&UVec3::splat(u32::MAX));
-        buf.align();
+        let field = |offset, size, alignment| FieldOffset {
+            offset,
+            size,
+            alignment,
+        };
 
-        #[rustfmt::skip]
+        let mut buf = Std140Bytes::with_debug_offsets();
+        buf.write(&1u32);
+
+        let snapshot = buf.snapshot();
+        buf.write(&Vec4::ONE).restore(snapshot);
+
+        assert_eq!(buf.len(), 4);
+        assert_eq!(buf.offsets(), [field(0, 4, 4)]);
+
+        buf.write_vec4_array_from_f32(&[[1.0; 4]; 2])
+            .write_array(&[1u32, 2]);
         assert_eq!(
-            buf.as_slice(),
-            &[
-                // x
-                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
-                // y
-                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
-                // z
-                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
-                // padding
-                0, 0, 0, 0, 
-                // x
-                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
-                // y
-                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
-                // z
-                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
-                // padding
-                0, 0, 0, 0, 
-            ]
+            buf.offsets(),
+            [field(0, 4, 4), field(16, 32, 16), field(48, 32, 16)]
         );
+
+        buf.align_to(64).rewind_padding().compact();
+        assert_eq!(buf.offsets().len(), 3);
+
+        let mut buf = Std140Bytes::with_padding_warnings(true);
+        buf.write(&1u32);
+
+        let snapshot = buf.snapshot();
+        buf.write(&Vec4::ONE).restore(snapshot);
+
+        assert!(buf.padding_warnings().is_empty());
     }
 
     #[test]
-    fn std140_scalar_and_vec3() {
+    fn std430_write_array_with_footer() {
+        use glam::Vec2;
+
+        let mut buf = Std430Bytes::new();
+        buf.write_array_with_footer(&[Vec2::ONE, Vec2::ONE]);
+
+        assert_eq!(buf.as_slice().len(), 20);
+        assert_eq!(
+            buf.as_slice()[..16],
+            *bytemuck::cast_slice::<f32, u8>(&[1.0; 4])
+        );
+        assert_eq!(buf.as_slice()[16..], 2u32.to_le_bytes());
+    }
+
+    #[test]
+    fn std140_clip_planes() {
+        use glam::Vec4;
+
         let mut buf = Std140Bytes::new();
+        buf.write_clip_planes(&[Vec4::X, Vec4::Y], 6);
 
-        buf.write(&u32::MAX);
-        buf.write(&UVec3::splat(u32::MAX));
-        buf.align();
+        let words: &[u32] = bytemuck::cast_slice(buf.as_slice());
 
-        #[rustfmt::skip]
+        assert_eq!(words.len(), 4 + 6 * 4);
+        assert_eq!(words[0], 2);
         assert_eq!(
-            buf.as_slice(),
-            &[
-                // scalar
-                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
-                // padding
-                0, 0, 0, 0, 
-                // padding
-                0, 0, 0, 0, 
-                // padding
-                0, 0, 0, 0, 
-                // x
-                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
-                // y
-                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
-                // z
-                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
-                // padding
-                0, 0, 0, 0, 
-            ]
+            words[4..12],
+            *bytemuck::cast_slice::<f32, u32>(&[1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0])
         );
+        assert!(words[12..].iter().all(|&word| word == 0));
     }
 
     #[test]
-    fn std140_scalar_array() {
+    fn std430_size_header() {
+        use glam::Vec4;
+
+        let mut buf = Std430Bytes::new();
+        let header = buf.reserve_size_header();
+
+        buf.write(&Vec4::ONE).write(&1.0f32).align();
+        buf.finalize_size_header(header).unwrap();
+
+        assert_eq!(buf.len(), 48);
+        assert_eq!(buf.as_slice()[..4], (buf.len() as u32).to_le_bytes());
+    }
+
+    #[test]
+    fn std140_write_value() {
+        use glam::Vec3;
+
+        let a = Vec3::new(1.0, 2.0, 3.0);
+
         let mut buf = Std140Bytes::new();
+        buf.write_value(a * 2.0).write_value(1.0f32);
 
-        buf.write_array(&[u32::MAX, u32::MAX]);
-        buf.align();
+        let mut expected = Std140Bytes::new();
+        expected.write(&Vec3::new(2.0, 4.0, 6.0)).write(&1.0f32);
+
+        assert_eq!(buf.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn std430_vec3_array_tight() {
+        use glam::Vec3;
+
+        let mut buf = Std430Bytes::new();
+        buf.write_vec3_array_tight(&[Vec3::new(1.0, 2.0, 3.0), Vec3::new(4.0, 5.0, 6.0)]);
 
-        #[rustfmt::skip]
         assert_eq!(
             buf.as_slice(),
-            &[
-                // scalar
-                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
-                // padding
-                0, 0, 0, 0, 
-                // padding
-                0, 0, 0, 0, 
-                // padding
-                0, 0, 0, 0, 
-                // scalar
-                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
-                // padding
-                0, 0, 0, 0, 
-                // padding
-                0, 0, 0, 0, 
-                // padding
-                0, 0, 0, 0, 
-            ]
+            bytemuck::cast_slice::<f32, u8>(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0])
         );
+        assert_eq!(buf.alignment(), 4);
     }
 
     #[test]
-    fn std430_scalar_array() {
+    fn align_to_zero() {
+        let mut buf = Std140Bytes::new();
+        buf.align().write(&1.0f32).align_to(0);
+
+        assert_eq!(buf.as_slice().len(), 4);
+        assert_eq!(
+            Std140SizeCounter::new()
+                .align()
+                .write(&Std140Bytes::new())
+                .len(),
+            0
+        );
+    }
+
+    #[test]
+    fn array_of_zero_alignment() {
+        let empty = [Std140Bytes::new(), Std140Bytes::new()];
+
+        let mut buf = Std140Bytes::new();
+        buf.write(&1u32).write_array(&empty);
+
+        let mut counter = Std140SizeCounter::new();
+        counter.write(&1u32).write_array(&empty);
+
+        assert_eq!(counter.len(), 4);
+        assert_eq!(buf.as_slice().len(), counter.len());
+
+        let mut batch = Std140Bytes::new();
+        batch.write(&1u32).write_array_batch(&[&empty, &empty[..1]]);
+
+        assert_eq!(batch.as_slice(), buf.as_slice());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "alignment 12 is not a power of two")]
+    fn align_to_non_power_of_two() {
+        Std140Bytes::new().write(&1.0f32).align_to(12);
+    }
+
+    #[test]
+    fn std430_write_interleaved() {
+        use glam::Vec3;
+
+        let positions = [Vec3::new(1.0, 2.0, 3.0), Vec3::new(4.0, 5.0, 6.0)];
+        let normals = [Vec3::X, Vec3::Y];
+
         let mut buf = Std430Bytes::new();
+        buf.write_interleaved(&positions, &normals).unwrap();
 
-        buf.write_array(&[u32::MAX, u32::MAX]);
-        buf.align();
+        // each `{ vec3, vec3 }` struct is 32 bytes, with the normal at 16
+        assert_eq!(buf.as_slice().len(), 64);
+        assert_eq!(buf.as_slice()[48..60], *normals[1].as_std430().as_slice());
 
-        #[rustfmt::skip]
         assert_eq!(
-            buf.as_slice(),
-            &[
-                // scalar
-                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
-                // scalar
-                u8::MAX, u8::MAX, u8::MAX, u8::MAX,
-            ]
+            buf.write_interleaved(&positions, &normals[..1]),
+            Err(GpuBytesError::LengthMismatch { left: 2, right: 1 })
         );
+        assert_eq!(buf.as_slice().len(), 64);
+    }
+
+    #[test]
+    fn std140_tuples() {
+        use glam::{Mat4, Vec3};
+
+        let mut manual = Std140Bytes::new();
+        manual.write(&7u32).write(&Vec3::ONE).align();
+        assert_eq!((7u32, Vec3::ONE).as_std140().as_slice(), manual.as_slice());
+
+        let mut manual = Std140Bytes::new();
+        manual.write(&3.0f32).align();
+        assert_eq!((3.0f32,).as_std140().as_slice(), manual.as_slice());
+
+        let block = (Vec3::ONE, 0.5f32, Mat4::IDENTITY);
+        assert_eq!(block.as_std140().as_slice().len(), 80);
+
+        let eight = (1u32, 2u32, 3u32, 4u32, 5u32, 6u32, 7u32, 8u32);
+        assert_eq!(eight.as_std430().as_slice().len(), 32);
+    }
+
+    #[test]
+    fn into_vec() {
+        use glam::{Mat4, Vec3};
+
+        let mut buf = Std140Bytes::new();
+        buf.write(&Vec3::ONE).write(&Mat4::IDENTITY);
+        let expected = buf.as_slice().to_vec();
+        assert_eq!(buf.into_vec(), expected);
+
+        let mut buf = Std430Bytes::new();
+        buf.write_array(&[1.0f32, 2.0, 3.0]);
+        let expected = buf.as_slice().to_vec();
+        assert_eq!(buf.into_vec(), expected);
     }
 }
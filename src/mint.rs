@@ -0,0 +1,88 @@
+use crate::{AsMetal, AsStd140, AsStd430, MetalBytes, Std140Bytes, Std430Bytes};
+
+// mint types are written exactly like the glam type they convert into, and only support Metal
+// when that glam type does
+macro_rules! mint_impl_std140_std430 {
+    (metal: $($datatype:ty => $glam:ty),+ $(,)?) => {
+        mint_impl_std140_std430!($($datatype => $glam),+);
+
+        $(
+            impl AsMetal for $datatype {
+                fn as_metal(&self) -> MetalBytes {
+                    <$glam>::from(*self).as_metal()
+                }
+            }
+        )+
+    };
+    ($($datatype:ty => $glam:ty),+ $(,)?) => {
+        $(
+            impl AsStd140 for $datatype {
+                fn as_std140(&self) -> Std140Bytes {
+                    <$glam>::from(*self).as_std140()
+                }
+            }
+
+            impl AsStd430 for $datatype {
+                fn as_std430(&self) -> Std430Bytes {
+                    <$glam>::from(*self).as_std430()
+                }
+            }
+        )+
+    };
+}
+
+mint_impl_std140_std430!(
+    metal:
+    mint::Vector2<f32> => glam::Vec2,
+    mint::Vector3<f32> => glam::Vec3,
+    mint::Vector4<f32> => glam::Vec4,
+    mint::Point2<f32> => glam::Vec2,
+    mint::Point3<f32> => glam::Vec3,
+    mint::Vector2<i32> => glam::IVec2,
+    mint::Vector3<i32> => glam::IVec3,
+    mint::Vector4<i32> => glam::IVec4,
+    mint::Vector2<u32> => glam::UVec2,
+    mint::Vector3<u32> => glam::UVec3,
+    mint::Vector4<u32> => glam::UVec4,
+    mint::ColumnMatrix2<f32> => glam::Mat2,
+    mint::ColumnMatrix3<f32> => glam::Mat3,
+    mint::ColumnMatrix4<f32> => glam::Mat4,
+);
+
+mint_impl_std140_std430!(
+    mint::Vector2<f64> => glam::DVec2,
+    mint::Vector3<f64> => glam::DVec3,
+    mint::Vector4<f64> => glam::DVec4,
+    mint::ColumnMatrix2<f64> => glam::DMat2,
+);
+
+#[cfg(test)]
+mod tests {
+    use glam::{Mat3, Vec3};
+
+    use super::*;
+
+    #[test]
+    fn vector3_matches_glam() {
+        let v = mint::Vector3::from([1.0f32, 2.0, 3.0]);
+
+        assert_eq!(
+            v.as_std140().as_slice(),
+            Vec3::new(1.0, 2.0, 3.0).as_std140().as_slice()
+        );
+        assert_eq!(
+            v.as_std430().as_slice(),
+            Vec3::new(1.0, 2.0, 3.0).as_std430().as_slice()
+        );
+    }
+
+    #[test]
+    fn column_matrix3_matches_glam() {
+        let mat = Mat3::from_cols_array(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0]);
+
+        assert_eq!(
+            mint::ColumnMatrix3::from(mat).as_std140().as_slice(),
+            mat.as_std140().as_slice()
+        );
+    }
+}
@@ -0,0 +1,73 @@
+//! Helpers for locking down the exact bytes of a buffer in tests.
+
+use std::fmt::Write;
+
+// the number of differing bytes listed before the rest are summarized
+const MAX_LISTED: usize = 16;
+
+/// Asserts that `actual` is exactly `expected`, e.g. `assert_bytes_eq(buf.as_slice(), &GOLDEN)`.
+///
+/// On a mismatch, the panic message lists the offset of each differing byte along with the
+/// expected and actual values, rather than printing both slices in full.
+#[track_caller]
+pub fn assert_bytes_eq(actual: &[u8], expected: &[u8]) {
+    if actual == expected {
+        return;
+    }
+
+    let mut message = format!(
+        "bytes differ (expected {} bytes, got {} bytes):",
+        expected.len(),
+        actual.len()
+    );
+
+    let differing = (0..actual.len().max(expected.len()))
+        .filter(|&i| actual.get(i) != expected.get(i))
+        .collect::<Vec<_>>();
+
+    for &offset in differing.iter().take(MAX_LISTED) {
+        let show = |byte: Option<&u8>| match byte {
+            Some(byte) => format!("{byte:#04x}"),
+            None => "nothing".to_string(),
+        };
+
+        write!(
+            message,
+            "\n  offset {offset}: expected {}, got {}",
+            show(expected.get(offset)),
+            show(actual.get(offset))
+        )
+        .unwrap();
+    }
+
+    if differing.len() > MAX_LISTED {
+        write!(message, "\n  ... and {} more", differing.len() - MAX_LISTED).unwrap();
+    }
+
+    panic!("{message}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Std140Bytes;
+
+    #[test]
+    fn matching_bytes() {
+        let mut buf = Std140Bytes::new();
+        buf.write(&1u32);
+
+        assert_bytes_eq(buf.as_slice(), &[1, 0, 0, 0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "bytes differ (expected 8 bytes, got 4 bytes):
+  offset 0: expected 0x02, got 0x01
+  offset 4: expected 0x03, got nothing")]
+    fn mismatched_bytes() {
+        let mut buf = Std140Bytes::new();
+        buf.write(&1u32);
+
+        assert_bytes_eq(buf.as_slice(), &[2, 0, 0, 0, 3, 0, 0, 0]);
+    }
+}
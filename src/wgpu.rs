@@ -0,0 +1,138 @@
+use std::borrow::Cow;
+
+use wgpu::util::DeviceExt;
+
+use crate::{GpuBytesError, Std140Bytes, Std430Bytes};
+
+// buffer sizes must be a multiple of `COPY_BUFFER_ALIGNMENT`, so the contents are padded with
+// zeros when needed
+fn padded_contents(bytes: &[u8]) -> Cow<'_, [u8]> {
+    let size = (bytes.len() as u64).next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) as usize;
+
+    if size == bytes.len() {
+        Cow::Borrowed(bytes)
+    } else {
+        let mut padded = bytes.to_vec();
+        padded.resize(size, 0);
+
+        Cow::Owned(padded)
+    }
+}
+
+fn create_buffer(
+    device: &wgpu::Device,
+    label: Option<&str>,
+    bytes: &[u8],
+    usage: wgpu::BufferUsages,
+) -> wgpu::Buffer {
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label,
+        contents: &padded_contents(bytes),
+        usage,
+    })
+}
+
+impl Std140Bytes {
+    /// Checks that the buffer can be bound as a uniform buffer with the given limits, i.e. that it
+    /// fits in `max_uniform_buffer_binding_size` and that its size is a multiple of 16.
+    pub fn validate_for_uniform(&self, limits: &wgpu::Limits) -> Result<(), GpuBytesError> {
+        let size = self.gpu_bytes.len();
+
+        if size as u64 > limits.max_uniform_buffer_binding_size {
+            return Err(GpuBytesError::ExceedsMaxSize {
+                size,
+                max_size: limits.max_uniform_buffer_binding_size as usize,
+            });
+        }
+
+        if !size.is_multiple_of(16) {
+            return Err(GpuBytesError::MisalignedLength {
+                len: size,
+                alignment: 16,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Creates a buffer with `UNIFORM | COPY_DST` usage holding these bytes.
+    pub fn create_uniform_buffer(
+        &self,
+        device: &wgpu::Device,
+        label: Option<&str>,
+    ) -> wgpu::Buffer {
+        create_buffer(
+            device,
+            label,
+            self.gpu_bytes.as_slice(),
+            wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        )
+    }
+}
+
+impl Std430Bytes {
+    /// Creates a buffer with `STORAGE | COPY_DST` usage holding these bytes.
+    pub fn create_storage_buffer(
+        &self,
+        device: &wgpu::Device,
+        label: Option<&str>,
+    ) -> wgpu::Buffer {
+        create_buffer(
+            device,
+            label,
+            self.gpu_bytes.as_slice(),
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec3, Vec4};
+
+    use super::*;
+    use crate::Fixed16;
+
+    #[test]
+    fn contents_padded_to_copy_alignment() {
+        let mut buf = Std430Bytes::new();
+        buf.write(&Fixed16::<8>(1.5));
+
+        assert_eq!(&*padded_contents(buf.as_slice()), &[0x80, 0x01, 0, 0]);
+        assert!(matches!(
+            padded_contents(&[1, 2, 3, 4]),
+            Cow::Borrowed(&[1, 2, 3, 4])
+        ));
+    }
+
+    #[test]
+    fn validate_for_uniform() {
+        let limits = wgpu::Limits {
+            max_uniform_buffer_binding_size: 32,
+            ..Default::default()
+        };
+
+        let mut buf = Std140Bytes::new();
+        buf.write(&Vec4::ONE).write(&Vec3::ONE);
+
+        assert_eq!(
+            buf.validate_for_uniform(&limits),
+            Err(GpuBytesError::MisalignedLength {
+                len: 28,
+                alignment: 16
+            })
+        );
+
+        buf.align();
+        assert_eq!(buf.validate_for_uniform(&limits), Ok(()));
+
+        buf.write(&Vec4::ONE);
+        assert_eq!(
+            buf.validate_for_uniform(&limits),
+            Err(GpuBytesError::ExceedsMaxSize {
+                size: 48,
+                max_size: 32
+            })
+        );
+    }
+}
@@ -0,0 +1,205 @@
+#![cfg(feature = "derive")]
+
+use glam::{Mat4, Vec3, Vec4};
+use gpu_bytes::{
+    AsMetal, AsStd140, AsStd430, FieldInfo, Layout, LayoutInfo, Std140Bytes, Std430Bytes,
+};
+
+#[derive(AsStd140, AsStd430)]
+struct Light {
+    position: Vec3,
+    intensity: f32,
+}
+
+#[derive(AsStd140, AsStd430)]
+struct Scene {
+    ambient: Vec4,
+    exposure: f32,
+    light: Light,
+}
+
+struct ManualLight {
+    position: Vec3,
+    intensity: f32,
+}
+
+impl AsStd140 for ManualLight {
+    fn as_std140(&self) -> Std140Bytes {
+        let mut buf = Std140Bytes::new();
+
+        buf.write(&self.position).write(&self.intensity);
+        buf.align();
+
+        buf
+    }
+}
+
+impl AsStd430 for ManualLight {
+    fn as_std430(&self) -> Std430Bytes {
+        let mut buf = Std430Bytes::new();
+
+        buf.write(&self.position).write(&self.intensity);
+        buf.align();
+
+        buf
+    }
+}
+
+struct ManualScene {
+    ambient: Vec4,
+    exposure: f32,
+    light: ManualLight,
+}
+
+impl AsStd140 for ManualScene {
+    fn as_std140(&self) -> Std140Bytes {
+        let mut buf = Std140Bytes::new();
+
+        buf.write(&self.ambient)
+            .write(&self.exposure)
+            .write(&self.light);
+        buf.align();
+
+        buf
+    }
+}
+
+impl AsStd430 for ManualScene {
+    fn as_std430(&self) -> Std430Bytes {
+        let mut buf = Std430Bytes::new();
+
+        buf.write(&self.ambient)
+            .write(&self.exposure)
+            .write(&self.light);
+        buf.align();
+
+        buf
+    }
+}
+
+#[test]
+fn derive_matches_manual_impl() {
+    let derived = Scene {
+        ambient: Vec4::new(0.1, 0.2, 0.3, 1.0),
+        exposure: 2.0,
+        light: Light {
+            position: Vec3::new(1.0, 2.0, 3.0),
+            intensity: 4.0,
+        },
+    };
+
+    let manual = ManualScene {
+        ambient: derived.ambient,
+        exposure: derived.exposure,
+        light: ManualLight {
+            position: derived.light.position,
+            intensity: derived.light.intensity,
+        },
+    };
+
+    assert_eq!(
+        derived.as_std140().as_slice(),
+        manual.as_std140().as_slice()
+    );
+    assert_eq!(
+        derived.as_std430().as_slice(),
+        manual.as_std430().as_slice()
+    );
+    assert_eq!(derived.as_std140().as_slice().len(), 48);
+}
+
+#[derive(AsStd140, AsStd430, AsMetal, LayoutInfo)]
+struct Material {
+    color: Vec3,
+    roughness: f32,
+    weights: [f32; 2],
+}
+
+#[derive(AsStd140, LayoutInfo)]
+struct Object {
+    transform: Mat4,
+    material: Material,
+}
+
+#[test]
+fn derive_layout_info() {
+    let object = Object {
+        transform: Mat4::IDENTITY,
+        material: Material {
+            color: Vec3::ONE,
+            roughness: 0.5,
+            weights: [1.0, 2.0],
+        },
+    };
+
+    assert_eq!(
+        Object::layout_info(Layout::Std140),
+        LayoutInfo {
+            name: "Object",
+            size: 112,
+            alignment: 16,
+            fields: vec![
+                FieldInfo {
+                    name: "transform",
+                    wgsl_type: "mat4x4<f32>".to_string(),
+                    offset: 0,
+                    size: 64,
+                },
+                FieldInfo {
+                    name: "material",
+                    wgsl_type: "Material".to_string(),
+                    offset: 64,
+                    size: 48,
+                },
+            ],
+        }
+    );
+
+    // std430 doesn't round the array elements up to 16 bytes
+    assert_eq!(
+        Material::layout_info(Layout::Std430).fields[2],
+        FieldInfo {
+            name: "weights",
+            wgsl_type: "array<f32, 2>".to_string(),
+            offset: 16,
+            size: 8,
+        }
+    );
+
+    // the layout is computed from the field types, and matches what is actually written
+    assert_eq!(
+        Object::layout_info(Layout::Std140).size,
+        object.as_std140().as_slice().len()
+    );
+    assert_eq!(
+        Material::layout_info(Layout::Metal).size,
+        object.material.as_metal().as_slice().len()
+    );
+}
+
+#[derive(AsStd140, AsStd430, AsMetal, LayoutInfo)]
+struct Sun {
+    intensity: f32,
+    direction: Vec3,
+}
+
+#[test]
+fn wgsl_struct_with_padding() {
+    assert_eq!(
+        Sun::layout_info(Layout::Std140).to_wgsl_struct(),
+        "struct Sun {
+    intensity: f32,
+    direction: vec3<f32>,
+}"
+    );
+    assert_eq!(
+        Sun::layout_info(Layout::Std140).to_wgsl_struct_with_padding(),
+        "struct Sun {
+    intensity: f32,
+    _pad0: u32,
+    _pad1: u32,
+    _pad2: u32,
+    direction: vec3<f32>,
+}"
+    );
+}
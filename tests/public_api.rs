@@ -0,0 +1,14 @@
+use gpu_bytes::{GpuBytes, Layout, Std430Bytes};
+
+#[test]
+fn gpu_bytes_with_explicit_layout() {
+    let mut buf = GpuBytes::new(Layout::Std430);
+    buf.write(&1u32).write(&glam::Vec2::ONE).align();
+
+    let mut expected = Std430Bytes::new();
+    expected.write(&1u32).write(&glam::Vec2::ONE).align();
+
+    assert_eq!(buf.layout(), Layout::Std430);
+    assert_eq!(buf.as_slice().len(), 16);
+    assert_eq!(buf.as_slice(), expected.as_slice());
+}